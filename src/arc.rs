@@ -0,0 +1,342 @@
+use std::f32::consts::TAU;
+
+use crate::drawable::{Drawable, VertexLayout};
+use crate::framebuffer::{Canvas, Color, Span, WHITE};
+use crate::line::{Line, LineAlgorithm};
+use crate::opengl::gl;
+use crate::transform::transform_points;
+use crate::viewport::Viewport;
+
+/// Slack in radians for pixels lying right on either end of an arc.
+const EPSILON: f32 = 1e-4;
+
+/// How the two ends of an arc are joined up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArcClosure {
+    /// Just the curve. Filled, it fills the same as a segment.
+    #[default]
+    Open,
+    /// A pie slice, with straight edges from both ends to the centre.
+    Sector,
+    /// A straight chord from one end to the other.
+    Segment,
+}
+
+impl std::str::FromStr for ArcClosure {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "open" => Ok(ArcClosure::Open),
+            "sector" | "pie" => Ok(ArcClosure::Sector),
+            "segment" | "chord" => Ok(ArcClosure::Segment),
+            _ => Err(format!("unknown arc closure `{}`", s)),
+        }
+    }
+}
+
+/// Part of a circle or ellipse, running counterclockwise from `start` to
+/// `end`. Angles are in radians from the positive x axis, measured at the
+/// centre. An `end` before `start` wraps round through zero, and equal
+/// angles give the whole curve.
+pub struct Arc {
+    points: Vec<[f32; 2]>,
+    centre: [i32; 2],
+    curve: Vec<[i32; 2]>,
+    disc: Vec<Span>,
+    start: f32,
+    sweep: f32,
+    closure: ArcClosure,
+    filled: bool,
+}
+
+impl Arc {
+    /// Cuts an arc out of a curve symmetric about `centre`, given every pixel
+    /// of its outline and the spans filling it. `Circle::arc` and
+    /// `Ellipse::arc` do this for the midpoint rasterizers.
+    pub fn new(
+        centre: [i32; 2],
+        curve: &[[f32; 2]],
+        disc: Vec<Span>,
+        start: f32,
+        end: f32,
+    ) -> Self {
+        let mut curve: Vec<[i32; 2]> = curve.iter().map(|&[x, y]| [x as i32, y as i32]).collect();
+        curve.sort_unstable();
+        curve.dedup();
+
+        let mut arc = Self {
+            points: vec![],
+            centre,
+            curve,
+            disc,
+            start,
+            sweep: (end - start).rem_euclid(TAU),
+            closure: ArcClosure::Open,
+            filled: false,
+        };
+        arc.collect_points();
+        arc
+    }
+
+    pub fn with_closure(mut self, closure: ArcClosure) -> Self {
+        self.closure = closure;
+        self.collect_points();
+        self
+    }
+
+    pub fn with_filled(mut self, filled: bool) -> Self {
+        self.filled = filled;
+        self.collect_points();
+        self
+    }
+
+    pub fn closure(&self) -> ArcClosure {
+        self.closure
+    }
+
+    pub fn is_filled(&self) -> bool {
+        self.filled
+    }
+
+    fn collect_points(&mut self) {
+        let mut points = vec![];
+        self.rasterize(&mut points, WHITE);
+        self.points = points;
+    }
+
+    fn is_full(&self) -> bool {
+        self.sweep == 0.0
+    }
+
+    /// How far round from `start` the pixel at `offset` from the centre is,
+    /// pixels just before `start` coming out slightly negative.
+    fn angle_of(&self, [dx, dy]: [i32; 2]) -> f32 {
+        let angle = (dy as f32).atan2(dx as f32) - self.start;
+        let angle = angle.rem_euclid(TAU);
+        if angle > TAU - EPSILON {
+            angle - TAU
+        } else {
+            angle
+        }
+    }
+
+    fn contains_angle(&self, offset: [i32; 2]) -> bool {
+        self.is_full() || self.angle_of(offset) <= self.sweep + EPSILON
+    }
+
+    fn offset(&self, [x, y]: [i32; 2]) -> [i32; 2] {
+        [x - self.centre[0], y - self.centre[1]]
+    }
+
+    /// The pixels of the curve between the two angles. Every pixel of the
+    /// full curve is tested on its own, so all eight octants of a circle and
+    /// all four quadrants of an ellipse are cut the same way.
+    fn curve_pixels(&self) -> Vec<[i32; 2]> {
+        self.curve
+            .iter()
+            .copied()
+            .filter(|&pixel| self.contains_angle(self.offset(pixel)))
+            .collect()
+    }
+
+    /// The first and last pixels of the arc, where the straight edges of a
+    /// sector or segment meet the curve.
+    fn ends(&self, curve: &[[i32; 2]]) -> Option<[[i32; 2]; 2]> {
+        if self.is_full() {
+            return None;
+        }
+        let by_angle = |&a: &&[i32; 2], &b: &&[i32; 2]| {
+            self.angle_of(self.offset(*a))
+                .total_cmp(&self.angle_of(self.offset(*b)))
+        };
+        let first = curve.iter().min_by(by_angle)?;
+        let last = curve.iter().max_by(by_angle)?;
+        Some([*first, *last])
+    }
+
+    /// Every pixel of the outline once: the curve, plus the edges to the
+    /// centre of a sector or the chord of a segment.
+    fn outline(&self) -> Vec<[i32; 2]> {
+        let mut pixels = self.curve_pixels();
+        if let Some([first, last]) = self.ends(&pixels) {
+            let edges = match self.closure {
+                ArcClosure::Open => vec![],
+                ArcClosure::Sector => vec![[self.centre, first], [self.centre, last]],
+                ArcClosure::Segment => vec![[first, last]],
+            };
+            let mut points = vec![];
+            for [from, to] in edges {
+                let from = [from[0] as f32, from[1] as f32];
+                let to = [to[0] as f32, to[1] as f32];
+                Line::new(from, to, LineAlgorithm::Bresenham).rasterize(&mut points, WHITE);
+            }
+            pixels.extend(points.iter().map(|&[x, y]| [x as i32, y as i32]));
+        }
+        pixels.sort_unstable();
+        pixels.dedup();
+        pixels
+    }
+
+    /// The filled arc as spans, bottom to top: the pixels of the filled
+    /// curve inside the sector's angles or on the curve's side of the chord,
+    /// together with the outline.
+    pub fn spans(&self) -> Vec<Span> {
+        let outline = self.outline();
+        let ends = self.ends(&self.curve_pixels());
+        let inside = |pixel: [i32; 2]| match (self.closure, ends) {
+            (_, None) => true,
+            (ArcClosure::Sector, _) => {
+                pixel == self.centre || self.contains_angle(self.offset(pixel))
+            }
+            (_, Some([first, last])) => {
+                let chord = [last[0] - first[0], last[1] - first[1]];
+                let to_pixel = [pixel[0] - first[0], pixel[1] - first[1]];
+                first != last && chord[0] * to_pixel[1] - chord[1] * to_pixel[0] <= 0
+            }
+        };
+
+        let mut pixels: Vec<[i32; 2]> = self
+            .disc
+            .iter()
+            .flat_map(|span| (span.x0..=span.x1).map(move |x| [x, span.y]))
+            .filter(|&pixel| inside(pixel))
+            .chain(outline)
+            .map(|[x, y]| [y, x])
+            .collect();
+        pixels.sort_unstable();
+        pixels.dedup();
+
+        let mut spans: Vec<Span> = vec![];
+        for [y, x] in pixels {
+            match spans.last_mut() {
+                Some(span) if span.y == y && span.x1 + 1 == x => span.x1 = x,
+                _ => spans.push(Span { y, x0: x, x1: x }),
+            }
+        }
+        spans
+    }
+
+    /// Plots the outline, or fills the arc if it is filled, straight into
+    /// `canvas`. No pixel is plotted twice.
+    pub fn rasterize(&self, canvas: &mut impl Canvas, color: Color) {
+        if self.filled {
+            for span in self.spans() {
+                canvas.fill_span(span, color);
+            }
+        } else {
+            for [x, y] in self.outline() {
+                canvas.plot(x, y, color);
+            }
+        }
+    }
+
+    pub fn points(&self) -> &[[f32; 2]] {
+        &self.points
+    }
+
+    pub fn get_normalized_coordinate(&self, viewport: &Viewport) -> Vec<f32> {
+        let window_to_ndc = viewport.window_to_ndc();
+        self.points
+            .iter()
+            .flat_map(|&item| {
+                let [x, y] = window_to_ndc.apply(item);
+                [
+                    x, y, 0.0, // Z
+                    1.0, // R
+                    1.0, // G
+                    1.0, // B
+                ]
+            })
+            .collect()
+    }
+
+    /// Applies a row-major 2D homogeneous matrix, or a `Transform2D`, to the
+    /// rasterized points.
+    /// The arc given to `rasterize` is left untouched.
+    pub fn transform(&mut self, matrix: impl Into<[[f32; 3]; 3]>) {
+        transform_points(&mut self.points, matrix.into());
+    }
+}
+
+impl Drawable for Arc {
+    fn vertices(&self, viewport: &Viewport) -> Vec<f32> {
+        self.get_normalized_coordinate(viewport)
+    }
+
+    fn layout(&self) -> VertexLayout {
+        VertexLayout::position_color(3)
+    }
+
+    fn primitive(&self) -> gl::types::GLenum {
+        gl::POINTS
+    }
+}
+
+#[test]
+fn arc_test() {
+    use std::collections::BTreeSet;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    use crate::circle::Circle;
+    use crate::ellipse::Ellipse;
+
+    let pixels = |points: &[[f32; 2]]| -> BTreeSet<[i32; 2]> {
+        points.iter().map(|&[x, y]| [x as i32, y as i32]).collect()
+    };
+    let quarters = [
+        (0.0, FRAC_PI_2),
+        (FRAC_PI_2, PI),
+        (PI, -FRAC_PI_2),
+        (-FRAC_PI_2, 0.0),
+    ];
+
+    // Quarter arcs, the last wrapping round through zero, make up the whole
+    // curve between them.
+    let circle = Circle::new([30, 30], 17);
+    let ellipse = Ellipse::new([30, 30], 23, 9);
+    for (whole, arcs) in [
+        (
+            circle.points(),
+            quarters.map(|(start, end)| circle.arc(start, end)),
+        ),
+        (
+            ellipse.points(),
+            quarters.map(|(start, end)| ellipse.arc(start, end)),
+        ),
+    ] {
+        let union: BTreeSet<_> = arcs.iter().flat_map(|arc| pixels(arc.points())).collect();
+        assert_eq!(union, pixels(whole));
+        assert!(arcs[3]
+            .points()
+            .iter()
+            .all(|&[x, y]| x >= 30.0 && y <= 30.0));
+    }
+    assert_eq!(
+        circle.arc(1.0, 1.0).points().len(),
+        pixels(circle.points()).len()
+    );
+
+    let wrapped = pixels(circle.arc(-FRAC_PI_4, FRAC_PI_4).points());
+    assert!(wrapped.contains(&[47, 30]) && !wrapped.contains(&[13, 30]));
+
+    // Filled sectors tile the disc, and hold their own outlines.
+    let disc = Circle::filled([30, 30], 17);
+    let mut tiled = BTreeSet::new();
+    for (start, end) in quarters {
+        let sector = disc.arc(start, end).with_closure(ArcClosure::Sector);
+        let outline = circle.arc(start, end).with_closure(ArcClosure::Sector);
+        assert!(pixels(outline.points()).is_subset(&pixels(sector.points())));
+        tiled.extend(pixels(sector.points()));
+    }
+    assert_eq!(tiled, pixels(disc.points()));
+
+    // Half a disc cut off along its diameter.
+    let half = disc.arc(0.0, PI).with_closure(ArcClosure::Segment);
+    let top: BTreeSet<_> = pixels(disc.points())
+        .into_iter()
+        .filter(|&[_, y]| y >= 30)
+        .collect();
+    assert_eq!(pixels(half.points()), top);
+    assert_eq!(half.points().len(), top.len());
+}
@@ -0,0 +1,126 @@
+use crate::transform3d::Transform3D;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Parallel projection of a view volume `height` units tall, with the
+    /// width following the camera's aspect ratio.
+    Orthographic { height: f32, near: f32, far: f32 },
+    /// Perspective projection with a vertical field of view in radians.
+    Perspective { fovy: f32, near: f32, far: f32 },
+}
+
+/// A look-at camera producing the view and projection matrices for the 3D
+/// shaders.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub eye: [f32; 3],
+    pub target: [f32; 3],
+    pub up: [f32; 3],
+    pub aspect: f32,
+    pub projection: Projection,
+}
+
+impl Camera {
+    pub fn orthographic(eye: [f32; 3], target: [f32; 3], height: f32) -> Self {
+        Self {
+            eye,
+            target,
+            projection: Projection::Orthographic {
+                height,
+                near: 0.1,
+                far: 100.0,
+            },
+            ..Self::default()
+        }
+    }
+
+    pub fn perspective(eye: [f32; 3], target: [f32; 3], fovy: f32) -> Self {
+        Self {
+            eye,
+            target,
+            projection: Projection::Perspective {
+                fovy,
+                near: 0.1,
+                far: 100.0,
+            },
+            ..Self::default()
+        }
+    }
+
+    pub fn view(&self) -> Transform3D {
+        Transform3D::look_at(self.eye, self.target, self.up)
+    }
+
+    pub fn projection(&self) -> Transform3D {
+        match self.projection {
+            Projection::Orthographic { height, near, far } => {
+                let (half_width, half_height) = (height * self.aspect / 2.0, height / 2.0);
+                Transform3D::orthographic(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    near,
+                    far,
+                )
+            }
+            Projection::Perspective { fovy, near, far } => {
+                Transform3D::perspective(fovy, self.aspect, near, far)
+            }
+        }
+    }
+
+    /// Projection applied after the view transformation.
+    pub fn view_projection(&self) -> Transform3D {
+        self.view().then(self.projection())
+    }
+}
+
+impl Default for Camera {
+    /// Looks down the negative Z axis from the origin at the `[-1, 1]` cube,
+    /// which leaves X and Y exactly where normalized device coordinates put
+    /// them.
+    fn default() -> Self {
+        Self {
+            eye: [0.0, 0.0, 0.0],
+            target: [0.0, 0.0, -1.0],
+            up: [0.0, 1.0, 0.0],
+            aspect: 1.0,
+            projection: Projection::Orthographic {
+                height: 2.0,
+                near: -1.0,
+                far: 1.0,
+            },
+        }
+    }
+}
+
+#[test]
+fn camera_test() {
+    use std::f32::consts::FRAC_PI_2;
+
+    use crate::viewport::{Rect, Viewport};
+
+    let close = |a: [f32; 3], b: [f32; 3]| (0..3).all(|i| (a[i] - b[i]).abs() < 1e-4);
+
+    // The eye ends up at the origin, looking down -Z at the target.
+    let camera = Camera::perspective([1.0, 2.0, 3.0], [4.0, 6.0, 3.0], FRAC_PI_2);
+    assert!(close(camera.view().apply(camera.eye), [0.0, 0.0, 0.0]));
+    assert!(close(camera.view().apply(camera.target), [0.0, 0.0, -5.0]));
+
+    // A surface twice as wide as it is tall shows twice as much across.
+    let viewport = Viewport::new(Rect::new(0.0, 0.0, 1600.0, 1200.0), 800, 400);
+    let camera = Camera {
+        aspect: viewport.aspect(),
+        ..Camera::perspective([0.0, 0.0, 0.0], [0.0, 0.0, -1.0], FRAC_PI_2)
+    };
+    let corner = camera.view_projection().apply([4.0, 2.0, -2.0]);
+    assert!(close([corner[0], corner[1], 0.0], [1.0, 1.0, 0.0]));
+
+    let camera = Camera {
+        aspect: viewport.aspect(),
+        ..Camera::orthographic([0.0, 0.0, 0.0], [0.0, 0.0, -1.0], 2.0)
+    };
+    let corner = camera.view_projection().apply([2.0, 1.0, -2.0]);
+    assert!(close([corner[0], corner[1], 0.0], [1.0, 1.0, 0.0]));
+}
@@ -1,32 +1,101 @@
-use crate::opengl;
-use crate::opengl::*;
-use ndarray::prelude::*;
+use crate::arc::Arc;
+use crate::drawable::{Drawable, VertexLayout};
+use crate::fill::symmetric_spans;
+use crate::framebuffer::{Canvas, Color, Coverage, Points, Span, WHITE};
+use crate::opengl::gl;
+use crate::transform::transform_points;
+use crate::viewport::Viewport;
 
 pub struct Circle {
     points: Vec<[f32; 2]>,
+    coverage: Vec<f32>,
     centre: [i32; 2],
     radius: i32,
+    filled: bool,
+    antialiased: bool,
 }
 
 impl Circle {
+    /// Every constructor panics if `radius` is negative.
     pub fn new(centre: [i32; 2], radius: i32) -> Self {
+        Self::with_style(centre, radius, false, false)
+    }
+
+    /// A solid disc, made of the same pixels as the outline and everything
+    /// between them.
+    pub fn filled(centre: [i32; 2], radius: i32) -> Self {
+        Self::with_style(centre, radius, true, false)
+    }
+
+    /// An outline with Wu's anti-aliasing, its coverage of every pixel in
+    /// `coverage`.
+    pub fn antialiased(centre: [i32; 2], radius: i32) -> Self {
+        Self::with_style(centre, radius, false, true)
+    }
+
+    fn with_style(centre: [i32; 2], radius: i32, filled: bool, antialiased: bool) -> Self {
+        assert!(radius >= 0, "a circle needs a radius of at least 0");
         let mut circle = Self {
             points: vec![],
+            coverage: vec![],
             centre,
             radius,
+            filled,
+            antialiased,
         };
 
-        circle.generate_midpoint_circle();
+        let mut points = Points::default();
+        circle.rasterize(&mut points, WHITE);
+        circle.points = points.points;
+        circle.coverage = points.coverage;
+
+        circle
+    }
+
+    /// Plots the circle outline, or the whole disc if it is filled, straight
+    /// into `canvas`. Anti-aliased, every pixel is blended once with the
+    /// most coverage any octant gave it.
+    pub fn rasterize(&self, canvas: &mut impl Canvas, color: Color) {
+        if self.filled {
+            for span in self.spans() {
+                canvas.fill_span(span, color);
+            }
+        } else if self.antialiased {
+            let [cx, cy] = self.centre;
+            let mut coverage = Coverage::default();
+            self.generate_wu_circle(|x, y, c| {
+                for [x, y] in [[x, y], [y, x]] {
+                    for [sx, sy] in [[1, 1], [-1, 1], [1, -1], [-1, -1]] {
+                        coverage.blend(cx + sx * x, cy + sy * y, color, c);
+                    }
+                }
+            });
+            coverage.rasterize(canvas, color);
+        } else {
+            self.generate_midpoint_circle(|x, y| self.push_symmetric_points(canvas, color, x, y));
+        }
+    }
 
-        return circle;
+    /// The disc as one span per row, bottom to top. The midpoint steps that
+    /// trace the outline give the span between each pair of symmetric points,
+    /// and every row keeps only the widest, so no pixel is covered twice.
+    pub fn spans(&self) -> Vec<Span> {
+        let mut half_widths = vec![0; self.radius as usize + 1];
+        self.generate_midpoint_circle(|x, y| {
+            half_widths[y as usize] = half_widths[y as usize].max(x);
+            half_widths[x as usize] = half_widths[x as usize].max(y);
+        });
+        symmetric_spans(self.centre, &half_widths)
     }
 
-    fn generate_midpoint_circle(&mut self) {
+    /// Walks the second octant, from the top of the circle until x meets y,
+    /// calling `visit` with every point's offset from the centre.
+    fn generate_midpoint_circle(&self, mut visit: impl FnMut(i32, i32)) {
         let mut x = 0;
         let mut y = self.radius;
         let mut d = 1 - self.radius;
 
-        self.push_symmetric_points(x, y);
+        visit(x, y);
 
         while y > x {
             if d < 0 {
@@ -36,270 +105,112 @@ impl Circle {
                 y -= 1;
             }
             x += 1;
-            self.push_symmetric_points(x, y);
+            visit(x, y);
         }
     }
 
-    fn push_symmetric_points(&mut self, x: i32, y: i32) {
-        self.points
-            .push([(self.centre[0] + x) as f32, (self.centre[1] + y) as f32]);
-        self.points
-            .push([(self.centre[0] - x) as f32, (self.centre[1] + y) as f32]);
-        self.points
-            .push([(self.centre[0] + x) as f32, (self.centre[1] - y) as f32]);
-        self.points
-            .push([(self.centre[0] - x) as f32, (self.centre[1] - y) as f32]);
-        self.points
-            .push([(self.centre[0] + y) as f32, (self.centre[1] + x) as f32]);
-        self.points
-            .push([(self.centre[0] - y) as f32, (self.centre[1] + x) as f32]);
-        self.points
-            .push([(self.centre[0] + y) as f32, (self.centre[1] - x) as f32]);
-        self.points
-            .push([(self.centre[0] - y) as f32, (self.centre[1] - x) as f32]);
+    /// The anti-aliased take on the second octant: every column from the top
+    /// of the circle until x meets y, with the circle's exact height there
+    /// shared between the two candidate pixels either side of it, each
+    /// getting more the closer the circle passes.
+    fn generate_wu_circle(&self, mut visit: impl FnMut(i32, i32, f32)) {
+        let r2 = self.radius * self.radius;
+        let mut x = 0;
+        while 2 * x * x <= r2 {
+            let y = ((r2 - x * x) as f32).sqrt();
+            let below = y.floor();
+            visit(x, below as i32, 1.0 - (y - below));
+            visit(x, below as i32 + 1, y - below);
+            x += 1;
+        }
+    }
+
+    fn push_symmetric_points(&self, canvas: &mut impl Canvas, color: Color, x: i32, y: i32) {
+        let [cx, cy] = self.centre;
+        canvas.plot(cx + x, cy + y, color);
+        canvas.plot(cx - x, cy + y, color);
+        canvas.plot(cx + x, cy - y, color);
+        canvas.plot(cx - x, cy - y, color);
+        canvas.plot(cx + y, cy + x, color);
+        canvas.plot(cx - y, cy + x, color);
+        canvas.plot(cx + y, cy - x, color);
+        canvas.plot(cx - y, cy - x, color);
+    }
+
+    /// The part of the circle from `start` to `end`, filled if the circle is.
+    /// Arcs are always cut from the aliased midpoint outline.
+    pub fn arc(&self, start: f32, end: f32) -> Arc {
+        let mut outline = vec![];
+        self.generate_midpoint_circle(|x, y| self.push_symmetric_points(&mut outline, WHITE, x, y));
+        Arc::new(self.centre, &outline, self.spans(), start, end).with_filled(self.filled)
     }
 
-    pub fn get_normalized_coordinate(&self) -> Vec<f32> {
-        return self
-            .points
+    pub fn points(&self) -> &[[f32; 2]] {
+        &self.points
+    }
+
+    /// How much of each pixel in `points` the outline covers, 1 unless it
+    /// is anti-aliased.
+    pub fn coverage(&self) -> &[f32] {
+        &self.coverage
+    }
+
+    /// Vertices laid out as `VertexLayout::position_color_alpha(3)`, the same
+    /// as `Line::get_normalized_coordinate`.
+    pub fn get_normalized_coordinate(&self, viewport: &Viewport) -> Vec<f32> {
+        let window_to_ndc = viewport.window_to_ndc();
+        self.points
             .iter()
-            .flat_map(|item| {
+            .zip(&self.coverage)
+            .flat_map(|(&item, &coverage)| {
+                let [x, y] = window_to_ndc.apply(item);
                 [
-                    (item[0] as f32 / 800.0) - 1.0,
-                    (item[1] as f32 / 600.0) - 1.0,
-                    0.0, // Z
+                    x, y, 0.0, // Z
                     1.0, // R
                     1.0, // G
                     1.0, // B
+                    coverage,
                 ]
             })
-            .collect();
+            .collect()
     }
 
-    pub fn get_2d_normalized_coordinate(&self) -> Vec<f32> {
-        return self
-            .points
+    pub fn get_2d_normalized_coordinate(&self, viewport: &Viewport) -> Vec<f32> {
+        let window_to_ndc = viewport.window_to_ndc();
+        self.points
             .iter()
-            .flat_map(|item| {
+            .flat_map(|&item| {
+                let [x, y] = window_to_ndc.apply(item);
                 [
-                    (item[0] as f32 / 800.0) - 1.0,
-                    (item[1] as f32 / 600.0) - 1.0,
-                    1.0, // R
+                    x, y, 1.0, // R
                     1.0, // G
                     1.0, // B
                 ]
             })
-            .collect();
+            .collect()
     }
 
-    pub fn transform(&mut self, matrix: [[f32; 3]; 3]) {
-        // let transformation_matrix = arr2(&matrix);
-        //
-        // let points = Array2::from(
-        //     self.points
-        //         .iter()
-        //         .map(|x| [x[0] as f32, x[1] as f32, 1.0])
-        //         .collect::<Vec<[f32; 3]>>(),
-        // );
-        // println!("{:?}",points.shape());
-        // println!("{:?}",transformation_matrix.shape());
-        //
-        // let transformed_points = transformation_matrix
-        //     .dot(&points)
-        //     .iter()
-        //     .map(|x| x.to_owned())
-        //     .collect::<Vec<f32>>();
-        //
-        // println!("{:?}",self.points[93]);
-        // println!("{:?}",transformed_points[93]);
-        //
-        // let mut new_points = Vec::new();
-        // for i in 0..self.points.len() / 3 {
-        //     new_points.push([transformed_points[i], transformed_points[i + 1]])
-        // }
-        //
-        //
-        // self.points = new_points;
+    /// Applies a row-major 2D homogeneous matrix, or a `Transform2D`, to the
+    /// rasterized points.
+    /// The outline given to `rasterize` is left untouched.
+    pub fn transform(&mut self, matrix: impl Into<[[f32; 3]; 3]>) {
+        transform_points(&mut self.points, matrix.into());
     }
+}
 
-    pub unsafe fn drawer(renderer: &mut crate::opengl::Renderer) -> () {
-        let vertex_shader =
-            opengl::create_shader(&renderer.gl, gl::VERTEX_SHADER, VERTEX_SHADER_SOURCE);
-        let fragment_shader =
-            create_shader(&renderer.gl, gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SOURCE);
-
-        renderer.program = Some(renderer.gl.CreateProgram());
-
-        renderer
-            .gl
-            .AttachShader(renderer.program.unwrap(), vertex_shader);
-
-        renderer
-            .gl
-            .AttachShader(renderer.program.unwrap(), fragment_shader);
-
-        renderer.gl.LinkProgram(renderer.program.unwrap());
-
-        renderer.gl.UseProgram(renderer.program.unwrap());
-
-        renderer.gl.GenVertexArrays(1, &mut renderer.vao);
-        renderer.gl.BindVertexArray(renderer.vao);
-
-        renderer.gl.GenBuffers(1, &mut renderer.vbo);
-        renderer.gl.BindBuffer(gl::ARRAY_BUFFER, renderer.vbo);
-
-        let circle = Circle::new([800, 600], 150);
-
-        let vertex_data = circle.get_2d_normalized_coordinate();
-
-        let vertex_indices: Vec<u32> = (0..vertex_data.len() as u32).collect();
-
-        let mut indices: gl::types::GLuint = std::mem::zeroed();
-
-        renderer.gl.GenBuffers(1, &mut indices);
-        renderer.gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, indices);
-        renderer.gl.BufferData(
-            gl::ELEMENT_ARRAY_BUFFER,
-            (vertex_indices.len() * std::mem::size_of::<u32>()) as gl::types::GLsizeiptr,
-            vertex_indices.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        );
-
-        renderer.gl.BufferData(
-            gl::ARRAY_BUFFER,
-            (vertex_data.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
-            vertex_data.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        );
-
-        // POSITION Attribute
-        let pos_attrib = renderer.gl.GetAttribLocation(
-            renderer.program.unwrap(),
-            b"position\0".as_ptr() as *const _,
-        );
-
-        renderer.gl.VertexAttribPointer(
-            pos_attrib as gl::types::GLuint,
-            2,
-            gl::FLOAT,
-            0,
-            5 * std::mem::size_of::<f32>() as gl::types::GLsizei,
-            std::ptr::null(),
-        );
-
-        // COLOR Attribute
-        let color_attrib = renderer
-            .gl
-            .GetAttribLocation(renderer.program.unwrap(), b"color\0".as_ptr() as *const _);
-
-        renderer.gl.VertexAttribPointer(
-            color_attrib as gl::types::GLuint,
-            3,
-            gl::FLOAT,
-            0,
-            5 * std::mem::size_of::<f32>() as gl::types::GLsizei,
-            (3 * std::mem::size_of::<f32>()) as *const _,
-        );
-
-        renderer
-            .gl
-            .EnableVertexAttribArray(pos_attrib as gl::types::GLuint);
-
-        renderer
-            .gl
-            .EnableVertexAttribArray(color_attrib as gl::types::GLuint);
-
-        renderer.gl.ClearColor(0.1, 0.1, 0.1, 0.9);
-
-        renderer.gl.Clear(gl::COLOR_BUFFER_BIT);
-
-        renderer.gl.DrawElements(
-            gl::POINTS,
-            vertex_indices.len() as i32,
-            gl::UNSIGNED_INT,
-            0 as *const _,
-        );
+impl Drawable for Circle {
+    fn vertices(&self, viewport: &Viewport) -> Vec<f32> {
+        self.get_normalized_coordinate(viewport)
     }
-}
 
-const VERTEX_SHADER_SOURCE: &[u8] = b"
-#version 100
-precision mediump float;
-attribute vec2 position;
-attribute vec3 color;
-varying vec3 v_color;
-
-const float PI = 3.141592654;
-
-const float ANGLE = PI/2.0;
-
-mat3 rotation_mat = mat3(
-    cos(ANGLE), sin(ANGLE),   0.0,
-    -sin(ANGLE), cos(ANGLE),   0.0,
-    0.0,      0.0,   1.0
-);
-
-mat3 translation_mat = mat3(
-    1.0, 0.0, 0.0,
-    0.0, 1.0, 0.0,
-    -0.5, -0.2, 1.0
-);
-
-mat3 scaling_mat = mat3(
-    2.0, 0.0, 0.0,
-    0.0, 2.0, 0.0,
-    0.0, 0.0, 1.0
-);
-
-// Reflection in XY
-mat3 reflection_mat_1 = mat3(
-    0.0, 1.0, 0.0,
-    1.0, 0.0, 0.0,
-    0.0, 0.0, 1.0
-);
-
-// Reflection in X = -Y
-mat3 reflection_mat_2 = mat3(
-    0.0, -1.0, 0.0,
-    -1.0, 0.0, 0.0,
-    0.0, 0.0, 1.0
-);
-
-// Reflection in X
-mat3 reflection_mat_3 = mat3(
-    1.0,  0.0, 0.0,
-    0.0, -1.0, 0.0,
-    0.0,  0.0, 1.0
-);
-
-// Reflection in Y
-mat3 reflection_mat_4 = mat3(
-    -1.0, 0.0, 0.0,
-    0.0,  1.0, 0.0,
-    0.0,  0.0, 1.0
-);
-
-// Reflection about origin
-mat3 reflection_mat_5 = mat3(
-    -1.0, 0.0, 0.0,
-    0.0,  -1.0, 0.0,
-    0.0,  0.0, 1.0
-);
-
-void main() {
-    gl_Position = vec4(translation_mat * vec3(position, 1.0), 1.0);
-    v_color = color;
-}
-\0";
-
-const FRAGMENT_SHADER_SOURCE: &[u8] = b"
-#version 100
-precision mediump float;
-varying vec3 v_color;
-void main() {
-    gl_FragColor = vec4(v_color, 1.0);
+    fn layout(&self) -> VertexLayout {
+        VertexLayout::position_color_alpha(3)
+    }
+
+    fn primitive(&self) -> gl::types::GLenum {
+        gl::POINTS
+    }
 }
-\0";
 
 #[test]
 fn circle_test() {
@@ -312,5 +223,95 @@ fn circle_test() {
         [0.0, 0.0, 1.0],
     ];
 
+    let before = circle.points().to_vec();
+    circle.transform(mat);
+    assert_eq!(circle.points(), &before[..]);
+
+    #[rustfmt::skip]
+    let mat = [
+        [1.0, 0.0, -500.0],
+        [0.0, 1.0, -500.0],
+        [0.0, 0.0,    1.0],
+    ];
+
     circle.transform(mat);
+    assert_eq!(circle.points(), Circle::new([0, 0], 150).points());
+}
+
+#[test]
+fn filled_circle_test() {
+    use crate::fill::{boundary_fill, Connectivity};
+    use crate::framebuffer::{Framebuffer, BLACK};
+
+    for radius in [0, 1, 2, 5, 12, 23] {
+        let filled = Circle::filled([24, 24], radius);
+        let spans = filled.spans();
+        assert_eq!(spans.len(), 2 * radius as usize + 1);
+        assert!(spans.windows(2).all(|pair| pair[0].y + 1 == pair[1].y));
+
+        // The same pixels as filling the inside of the outline.
+        let mut expected = Framebuffer::new(48, 48);
+        Circle::new([24, 24], radius).rasterize(&mut expected, WHITE);
+        if radius > 0 {
+            boundary_fill(&mut expected, [24, 24], WHITE, WHITE, Connectivity::Four);
+        }
+        let mut actual = Framebuffer::new(48, 48);
+        filled.rasterize(&mut actual, WHITE);
+        assert_eq!(actual, expected, "radius {radius}");
+
+        let lit = actual.pixels().iter().filter(|&&p| p != BLACK).count();
+        assert_eq!(filled.points().len(), lit);
+    }
+}
+
+#[test]
+fn antialiased_circle_test() {
+    use std::collections::{BTreeSet, HashMap};
+
+    for radius in [0, 1, 4, 15, 40, 101] {
+        let circle = Circle::antialiased([0, 0], radius);
+        assert_eq!(circle.coverage().len(), circle.points().len());
+        assert!(circle.coverage().iter().all(|&c| c > 0.0 && c <= 1.0));
+
+        // Each pixel once, on either side of the true circle.
+        let pixels: BTreeSet<_> = circle
+            .points()
+            .iter()
+            .map(|&[x, y]| [x as i32, y as i32])
+            .collect();
+        assert_eq!(pixels.len(), circle.points().len());
+        for &[x, y] in circle.points() {
+            assert!(
+                (x.hypot(y) - radius as f32).abs() < 1.0,
+                "radius {radius}: {x},{y}"
+            );
+        }
+
+        // Every column of the top octant shares one pixel's worth between the
+        // two either side of the circle, weighted towards the nearer.
+        let coverage: HashMap<[i32; 2], f32> = circle
+            .points()
+            .iter()
+            .map(|&[x, y]| [x as i32, y as i32])
+            .zip(circle.coverage().iter().copied())
+            .collect();
+        for x in (0..).take_while(|x| (x + 1) * (x + 1) * 2 < radius * radius) {
+            let ys: Vec<_> = (x + 1..=radius + 1)
+                .filter(|&y| coverage.contains_key(&[x, y]))
+                .collect();
+            let total: f32 = ys.iter().map(|&y| coverage[&[x, y]]).sum();
+            let mean: f32 = ys.iter().map(|&y| y as f32 * coverage[&[x, y]]).sum();
+            let exact = ((radius * radius - x * x) as f32).sqrt();
+            assert!(
+                (total - 1.0).abs() < 1e-4 && (mean - exact).abs() < 1e-4,
+                "radius {radius}, column {x}"
+            );
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "a radius of at least 0")]
+fn negative_radius_test() {
+    Circle::antialiased([0, 0], -3);
 }
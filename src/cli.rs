@@ -0,0 +1,368 @@
+use std::path::PathBuf;
+
+use crate::arc::ArcClosure;
+use crate::dash::Dash;
+use crate::line::LineAlgorithm;
+use crate::stroke::Stroke;
+
+pub const USAGE: &str = "\
+Usage: biopix [DEMO] [OPTIONS]
+
+Demos (default: cube):
+  line     [--from X,Y] [--to X,Y] [--algorithm dda|bresenham|midpoint|wu]
+           [--width W] [--cap butt|round|square] [--join miter|bevel|round]
+           [--dash ON,OFF,...]
+  circle   [--centre X,Y] [--radius R] [--fill | --antialias]
+           [--arc START,END] [--close open|sector|segment]
+  ellipse  [--centre X,Y] [--semi-axes A,B] [--rotation ANGLE]
+           [--fill | --antialias]
+           [--arc START,END] [--close open|sector|segment]
+  flag
+  cube
+
+Options:
+  -o, --output FILE   Save a PNG instead of opening a window
+      --size W,H      Size of the saved image (default: 1600,1200)
+  -h, --help          Print this message
+
+Coordinates have their origin at the bottom left. A window shows the
+1600x1200 world the labs use, a saved image uses its own pixels. Arc and
+rotation angles are in degrees, counterclockwise from the positive x axis.
+`--antialias` draws whole outlines only, so it cannot be used with `--arc`.";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Demo {
+    Line {
+        from: [f32; 2],
+        to: [f32; 2],
+        algorithm: LineAlgorithm,
+        /// Draws a thick line instead of the algorithm's hairline.
+        stroke: Option<Stroke>,
+        dash: Option<Dash>,
+    },
+    Circle {
+        centre: [i32; 2],
+        radius: i32,
+        filled: bool,
+        /// Splits each outline pixel's intensity with its neighbour, Wu style.
+        antialiased: bool,
+        /// Start and end angles in radians, drawing only part of the curve.
+        arc: Option<[f32; 2]>,
+        closure: ArcClosure,
+    },
+    Ellipse {
+        centre: [i32; 2],
+        a: i32,
+        b: i32,
+        /// Counterclockwise, in radians.
+        rotation: f32,
+        filled: bool,
+        /// Splits each outline pixel's intensity with its neighbour, Wu style.
+        antialiased: bool,
+        /// Start and end angles in radians, drawing only part of the curve.
+        arc: Option<[f32; 2]>,
+        closure: ArcClosure,
+    },
+    Flag,
+    Cube,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Output {
+    Window,
+    Image { path: PathBuf, size: [u32; 2] },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Run { demo: Demo, output: Output },
+    Help,
+}
+
+/// Parses the arguments after the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter().peekable();
+
+    let demo_name = match args.peek() {
+        Some(arg) if !arg.starts_with('-') => args.next().unwrap(),
+        _ => "cube".to_owned(),
+    };
+    let mut demo = match demo_name.as_str() {
+        "line" => Demo::Line {
+            from: [200.0, 200.0],
+            to: [1400.0, 900.0],
+            algorithm: LineAlgorithm::DDA,
+            stroke: None,
+            dash: None,
+        },
+        "circle" => Demo::Circle {
+            centre: [800, 600],
+            radius: 150,
+            filled: false,
+            antialiased: false,
+            arc: None,
+            closure: ArcClosure::Open,
+        },
+        "ellipse" => Demo::Ellipse {
+            centre: [800, 600],
+            a: 300,
+            b: 150,
+            rotation: 0.0,
+            filled: false,
+            antialiased: false,
+            arc: None,
+            closure: ArcClosure::Open,
+        },
+        "flag" => Demo::Flag,
+        "cube" => Demo::Cube,
+        _ => return Err(format!("unknown demo `{}`", demo_name)),
+    };
+
+    let mut path = None;
+    let mut size = [1600, 1200];
+
+    while let Some(flag) = args.next() {
+        if flag == "-h" || flag == "--help" {
+            return Ok(Command::Help);
+        }
+
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{}` needs a value", flag))
+        };
+
+        match (&mut demo, flag.as_str()) {
+            (_, "-o" | "--output") => path = Some(PathBuf::from(value()?)),
+            (_, "--size") => size = parse_pair(&value()?)?,
+            (Demo::Line { from, .. }, "--from") => *from = parse_pair(&value()?)?,
+            (Demo::Line { to, .. }, "--to") => *to = parse_pair(&value()?)?,
+            (Demo::Line { algorithm, .. }, "--algorithm") => *algorithm = value()?.parse()?,
+            (Demo::Line { dash, .. }, "--dash") => *dash = Some(parse_dash(&value()?)?),
+            (Demo::Line { stroke, .. }, "--width") => {
+                stroke.get_or_insert_with(Stroke::default).width = parse_number(&value()?)?
+            }
+            (Demo::Line { stroke, .. }, "--cap") => {
+                stroke.get_or_insert_with(Stroke::default).cap = value()?.parse()?
+            }
+            (Demo::Line { stroke, .. }, "--join") => {
+                stroke.get_or_insert_with(Stroke::default).join = value()?.parse()?
+            }
+            (Demo::Circle { centre, .. } | Demo::Ellipse { centre, .. }, "--centre") => {
+                *centre = parse_pair(&value()?)?
+            }
+            (Demo::Circle { radius, .. }, "--radius") => *radius = parse_number(&value()?)?,
+            (Demo::Ellipse { a, b, .. }, "--semi-axes") => [*a, *b] = parse_pair(&value()?)?,
+            (Demo::Ellipse { rotation, .. }, "--rotation") => {
+                *rotation = parse_number::<f32>(&value()?)?.to_radians()
+            }
+            (Demo::Circle { filled, .. } | Demo::Ellipse { filled, .. }, "--fill") => {
+                *filled = true
+            }
+            (
+                Demo::Circle { antialiased, .. } | Demo::Ellipse { antialiased, .. },
+                "--antialias",
+            ) => *antialiased = true,
+            (Demo::Circle { arc, .. } | Demo::Ellipse { arc, .. }, "--arc") => {
+                *arc = Some(parse_pair::<f32>(&value()?)?.map(f32::to_radians))
+            }
+            (Demo::Circle { closure, .. } | Demo::Ellipse { closure, .. }, "--close") => {
+                *closure = value()?.parse()?
+            }
+            _ => return Err(format!("unexpected argument `{}` for {}", flag, demo_name)),
+        }
+    }
+
+    if let Demo::Circle { radius: ..0, .. } = demo {
+        return Err("the radius cannot be negative".to_owned());
+    }
+    if let Demo::Ellipse { a, b, .. } = demo {
+        if a < 0 || b < 0 {
+            return Err("the semi-axes cannot be negative".to_owned());
+        }
+    }
+    if let Demo::Circle {
+        filled: true,
+        antialiased: true,
+        ..
+    }
+    | Demo::Ellipse {
+        filled: true,
+        antialiased: true,
+        ..
+    } = demo
+    {
+        return Err(format!(
+            "`--fill` and `--antialias` cannot be used together for {}",
+            demo_name
+        ));
+    }
+    if let Demo::Circle {
+        antialiased: true,
+        arc: Some(_),
+        ..
+    }
+    | Demo::Ellipse {
+        antialiased: true,
+        arc: Some(_),
+        ..
+    } = demo
+    {
+        return Err(format!(
+            "arcs cannot be anti-aliased, drop `--antialias` for {}",
+            demo_name
+        ));
+    }
+
+    let output = match path {
+        Some(path) => Output::Image { path, size },
+        None => Output::Window,
+    };
+
+    Ok(Command::Run { demo, output })
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid number `{}`", value))
+}
+
+/// Parses `ON,OFF,...` pixel lengths.
+fn parse_dash(value: &str) -> Result<Dash, String> {
+    let lengths = value
+        .split(',')
+        .map(parse_number)
+        .collect::<Result<Vec<u32>, _>>()?;
+    if lengths.iter().sum::<u32>() == 0 {
+        return Err(format!("dash pattern `{}` has no length", value));
+    }
+    Ok(Dash::new(&lengths))
+}
+
+/// Parses `X,Y`.
+fn parse_pair<T: std::str::FromStr>(value: &str) -> Result<[T; 2], String> {
+    match value.split_once(',') {
+        Some((x, y)) => Ok([parse_number(x)?, parse_number(y)?]),
+        None => Err(format!("expected X,Y but got `{}`", value)),
+    }
+}
+
+#[test]
+fn parse_test() {
+    use crate::stroke::LineCap;
+
+    let parse_str = |args: &str| parse(args.split_whitespace().map(str::to_owned));
+
+    assert_eq!(
+        parse_str(""),
+        Ok(Command::Run {
+            demo: Demo::Cube,
+            output: Output::Window
+        })
+    );
+    assert_eq!(
+        parse_str("line --from 10,20 --to 30.5,40 --algorithm bresenham -o line.png"),
+        Ok(Command::Run {
+            demo: Demo::Line {
+                from: [10.0, 20.0],
+                to: [30.5, 40.0],
+                algorithm: LineAlgorithm::Bresenham,
+                stroke: None,
+                dash: None,
+            },
+            output: Output::Image {
+                path: PathBuf::from("line.png"),
+                size: [1600, 1200],
+            },
+        })
+    );
+    assert_eq!(
+        parse_str("ellipse --semi-axes 40,20 --size 200,100 --output e.png"),
+        Ok(Command::Run {
+            demo: Demo::Ellipse {
+                centre: [800, 600],
+                a: 40,
+                b: 20,
+                rotation: 0.0,
+                filled: false,
+                antialiased: false,
+                arc: None,
+                closure: ArcClosure::Open,
+            },
+            output: Output::Image {
+                path: PathBuf::from("e.png"),
+                size: [200, 100],
+            },
+        })
+    );
+    assert_eq!(
+        parse_str("line --width 8 --cap round"),
+        Ok(Command::Run {
+            demo: Demo::Line {
+                from: [200.0, 200.0],
+                to: [1400.0, 900.0],
+                algorithm: LineAlgorithm::DDA,
+                stroke: Some(Stroke {
+                    width: 8.0,
+                    cap: LineCap::Round,
+                    ..Stroke::default()
+                }),
+                dash: None,
+            },
+            output: Output::Window,
+        })
+    );
+    assert!(matches!(
+        parse_str("line --dash 4,2"),
+        Ok(Command::Run {
+            demo: Demo::Line { dash: Some(dash), .. },
+            ..
+        }) if dash.lengths() == [4, 2]
+    ));
+    assert!(parse_str("line --dash 0,0").is_err());
+    assert!(matches!(
+        parse_str("circle --fill --radius 20"),
+        Ok(Command::Run {
+            demo: Demo::Circle {
+                radius: 20,
+                filled: true,
+                ..
+            },
+            ..
+        })
+    ));
+    assert!(matches!(
+        parse_str("ellipse --arc 90,180 --close pie"),
+        Ok(Command::Run {
+            demo: Demo::Ellipse {
+                arc: Some([start, end]),
+                closure: ArcClosure::Sector,
+                ..
+            },
+            ..
+        }) if start == std::f32::consts::FRAC_PI_2 && end == std::f32::consts::PI
+    ));
+    assert!(matches!(
+        parse_str("circle --antialias"),
+        Ok(Command::Run {
+            demo: Demo::Circle {
+                filled: false,
+                antialiased: true,
+                ..
+            },
+            ..
+        })
+    ));
+    assert!(parse_str("circle --radius -3").is_err());
+    assert!(parse_str("ellipse --semi-axes 4,-2").is_err());
+    assert!(parse_str("circle --fill --antialias").is_err());
+    assert!(parse_str("ellipse --antialias --fill").is_err());
+    assert!(parse_str("circle --antialias --arc 0,90").is_err());
+    assert!(parse_str("circle --close square").is_err());
+    assert!(parse_str("circle --rotation 30").is_err());
+    assert_eq!(parse_str("circle --help"), Ok(Command::Help));
+    assert!(parse_str("circle --from 1,2").is_err());
+    assert!(parse_str("circle --radius").is_err());
+    assert!(parse_str("square").is_err());
+}
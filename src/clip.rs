@@ -0,0 +1,541 @@
+use std::ops::{BitAnd, BitOr};
+
+use crate::viewport::Rect;
+
+/// Cohen–Sutherland region code of a point: one bit for every side of the
+/// window the point lies beyond.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Outcode(u8);
+
+impl Outcode {
+    pub const INSIDE: Outcode = Outcode(0);
+    pub const LEFT: Outcode = Outcode(1);
+    pub const RIGHT: Outcode = Outcode(2);
+    pub const BOTTOM: Outcode = Outcode(4);
+    pub const TOP: Outcode = Outcode(8);
+
+    pub fn of(point: [f32; 2], window: &Rect) -> Self {
+        let mut code = Self::INSIDE;
+        if point[0] < window.xmin {
+            code = code | Self::LEFT;
+        } else if point[0] > window.xmax {
+            code = code | Self::RIGHT;
+        }
+        if point[1] < window.ymin {
+            code = code | Self::BOTTOM;
+        } else if point[1] > window.ymax {
+            code = code | Self::TOP;
+        }
+        code
+    }
+
+    /// The code as the usual four bit number, `TBRL` from high to low.
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn is_inside(self) -> bool {
+        self == Self::INSIDE
+    }
+
+    pub fn contains(self, other: Outcode) -> bool {
+        self & other == other
+    }
+}
+
+impl BitOr for Outcode {
+    type Output = Outcode;
+
+    fn bitor(self, rhs: Outcode) -> Outcode {
+        Outcode(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Outcode {
+    type Output = Outcode;
+
+    fn bitand(self, rhs: Outcode) -> Outcode {
+        Outcode(self.0 & rhs.0)
+    }
+}
+
+/// The segment and its endpoints' outcodes at the start of one pass of the
+/// Cohen–Sutherland loop.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CohenSutherlandStep {
+    pub segment: [[f32; 2]; 2],
+    pub outcodes: [Outcode; 2],
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CohenSutherland {
+    /// Every pass, ending with the one that accepted or rejected the segment.
+    pub steps: Vec<CohenSutherlandStep>,
+    /// The part of the segment inside the window, if any.
+    pub segment: Option<[[f32; 2]; 2]>,
+}
+
+/// Clips the segment from `p0` to `p1` to `window` by repeatedly cutting off
+/// the part beyond one of the sides an endpoint is outside of, until both
+/// endpoints are inside (trivial accept) or beyond a common side (trivial
+/// reject).
+pub fn cohen_sutherland(p0: [f32; 2], p1: [f32; 2], window: &Rect) -> CohenSutherland {
+    let mut segment = [p0, p1];
+    let mut steps = vec![];
+
+    loop {
+        let outcodes = segment.map(|point| Outcode::of(point, window));
+        steps.push(CohenSutherlandStep { segment, outcodes });
+
+        if (outcodes[0] | outcodes[1]).is_inside() {
+            return CohenSutherland {
+                steps,
+                segment: Some(segment),
+            };
+        }
+        if !(outcodes[0] & outcodes[1]).is_inside() {
+            return CohenSutherland {
+                steps,
+                segment: None,
+            };
+        }
+
+        // Move an endpoint that is outside onto the side it is beyond.
+        let end = if outcodes[0].is_inside() { 1 } else { 0 };
+        let outcode = outcodes[end];
+        let [[x0, y0], [x1, y1]] = segment;
+        segment[end] = if outcode.contains(Outcode::TOP) {
+            [x0 + (x1 - x0) * (window.ymax - y0) / (y1 - y0), window.ymax]
+        } else if outcode.contains(Outcode::BOTTOM) {
+            [x0 + (x1 - x0) * (window.ymin - y0) / (y1 - y0), window.ymin]
+        } else if outcode.contains(Outcode::RIGHT) {
+            [window.xmax, y0 + (y1 - y0) * (window.xmax - x0) / (x1 - x0)]
+        } else {
+            [window.xmin, y0 + (y1 - y0) * (window.xmin - x0) / (x1 - x0)]
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiangBarsky {
+    /// `p` for the left, right, bottom and top sides. The segment runs from
+    /// outside to inside a side where its `p` is negative.
+    pub p: [f32; 4],
+    /// `q` for the same sides, the endpoint's distance inside each one.
+    pub q: [f32; 4],
+    /// Parameter where the segment enters the window, the largest `q / p`
+    /// over the sides with negative `p`, and 0 at least.
+    pub t0: f32,
+    /// Parameter where it leaves, the smallest `q / p` over the sides with
+    /// positive `p`, and 1 at most.
+    pub t1: f32,
+    pub segment: Option<[[f32; 2]; 2]>,
+}
+
+/// Clips the segment `p0 + t * (p1 - p0)` to `window` by narrowing the range
+/// of `t` against each side in turn.
+pub fn liang_barsky(p0: [f32; 2], p1: [f32; 2], window: &Rect) -> LiangBarsky {
+    let dx = p1[0] - p0[0];
+    let dy = p1[1] - p0[1];
+    let p = [-dx, dx, -dy, dy];
+    let q = [
+        p0[0] - window.xmin,
+        window.xmax - p0[0],
+        p0[1] - window.ymin,
+        window.ymax - p0[1],
+    ];
+
+    let mut t0: f32 = 0.0;
+    let mut t1: f32 = 1.0;
+    let mut visible = true;
+    for (&p, &q) in p.iter().zip(&q) {
+        if p == 0.0 {
+            // Parallel to this side, and wholly outside it if q < 0.
+            visible &= q >= 0.0;
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    visible &= t0 <= t1;
+
+    let at = |t: f32| [p0[0] + t * dx, p0[1] + t * dy];
+    LiangBarsky {
+        p,
+        q,
+        t0,
+        t1,
+        segment: visible.then(|| [at(t0), at(t1)]),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CyrusBeck {
+    /// Parameter where the segment enters the window, 0 if it starts inside.
+    pub t_enter: f32,
+    /// Parameter where it leaves, 1 if it ends inside.
+    pub t_leave: f32,
+    pub segment: Option<[[f32; 2]; 2]>,
+}
+
+/// Clips the segment `p0 + t * (p1 - p0)` to the convex polygon `window`,
+/// given in either winding order. Each edge's inward normal tells whether
+/// the segment crosses that edge going in or out, which bounds `t` from
+/// below or above the same way Liang–Barsky does for the sides of a
+/// rectangle.
+pub fn cyrus_beck(p0: [f32; 2], p1: [f32; 2], window: &[[f32; 2]]) -> CyrusBeck {
+    let d = [p1[0] - p0[0], p1[1] - p0[1]];
+
+    let area = signed_area(window);
+
+    let mut t_enter: f32 = 0.0;
+    let mut t_leave: f32 = 1.0;
+    let mut visible = area != 0.0;
+    for i in 0..window.len() {
+        let [a, b] = [window[i], window[(i + 1) % window.len()]];
+        let normal = [
+            -(b[1] - a[1]) * area.signum(),
+            (b[0] - a[0]) * area.signum(),
+        ];
+        // How far p0 is inside the edge, and how fast the segment moves in.
+        let inside = normal[0] * (p0[0] - a[0]) + normal[1] * (p0[1] - a[1]);
+        let inwards = normal[0] * d[0] + normal[1] * d[1];
+
+        if inwards == 0.0 {
+            visible &= inside >= 0.0;
+        } else if inwards > 0.0 {
+            t_enter = t_enter.max(-inside / inwards);
+        } else {
+            t_leave = t_leave.min(-inside / inwards);
+        }
+    }
+    visible &= t_enter <= t_leave;
+
+    let at = |t: f32| [p0[0] + t * d[0], p0[1] + t * d[1]];
+    CyrusBeck {
+        t_enter,
+        t_leave,
+        segment: visible.then(|| [at(t_enter), at(t_leave)]),
+    }
+}
+
+/// Clips `subject` to the convex polygon `clip`, given in either winding
+/// order, one edge of `clip` at a time: every pass keeps the part of the
+/// polygon on the inside of that edge, adding a vertex wherever the outline
+/// crosses it.
+///
+/// Always returns a single polygon, which is empty if nothing is left. Where a
+/// concave subject is cut in several pieces they stay joined along the
+/// clip edge; use `weiler_atherton` to get them separately.
+pub fn sutherland_hodgman(subject: &[[f32; 2]], clip: &[[f32; 2]]) -> Vec<[f32; 2]> {
+    let orientation = signed_area(clip).signum();
+    if orientation == 0.0 {
+        return vec![];
+    }
+
+    let mut output = subject.to_vec();
+    for i in 0..clip.len() {
+        let [a, b] = [clip[i], clip[(i + 1) % clip.len()]];
+        let inside = |p: [f32; 2]| orientation * cross(sub(b, a), sub(p, a)) >= 0.0;
+
+        let input = std::mem::take(&mut output);
+        for (j, &current) in input.iter().enumerate() {
+            let previous = input[(j + input.len() - 1) % input.len()];
+            match (inside(previous), inside(current)) {
+                (true, true) => output.push(current),
+                (true, false) => output.push(line_intersection(previous, current, a, b)),
+                (false, true) => {
+                    output.push(line_intersection(previous, current, a, b));
+                    output.push(current);
+                }
+                (false, false) => {}
+            }
+        }
+    }
+    output
+}
+
+/// Clips `subject` to `clip`, either of which may be concave, returning
+/// every separate piece of their intersection, counter-clockwise.
+///
+/// Both outlines are walked with the points where they cross inserted. Each
+/// piece starts where the subject enters the clip polygon, follows the
+/// subject until it leaves, then the clip polygon until the subject enters
+/// again, and so on until it is back at the start.
+///
+/// Vertices lying exactly on the other polygon's outline are not handled
+/// specially and can make the result wrong.
+pub fn weiler_atherton(subject: &[[f32; 2]], clip: &[[f32; 2]]) -> Vec<Vec<[f32; 2]>> {
+    let counter_clockwise = |polygon: &[[f32; 2]]| {
+        let mut polygon = polygon.to_vec();
+        if signed_area(&polygon) < 0.0 {
+            polygon.reverse();
+        }
+        polygon
+    };
+    if signed_area(subject) == 0.0 || signed_area(clip) == 0.0 {
+        return vec![];
+    }
+    let subject = counter_clockwise(subject);
+    let clip = counter_clockwise(clip);
+
+    struct Crossing {
+        point: [f32; 2],
+        /// Whether the subject goes into the clip polygon here.
+        entering: bool,
+    }
+
+    #[derive(Clone, Copy)]
+    enum Node {
+        Vertex([f32; 2]),
+        Crossing(usize),
+    }
+
+    // Crossings on each edge of either polygon, with the parameter along the
+    // edge to sort them by.
+    let mut crossings = vec![];
+    let mut on_subject = vec![vec![]; subject.len()];
+    let mut on_clip = vec![vec![]; clip.len()];
+    for i in 0..subject.len() {
+        let [a, b] = [subject[i], subject[(i + 1) % subject.len()]];
+        for j in 0..clip.len() {
+            let [c, d] = [clip[j], clip[(j + 1) % clip.len()]];
+            let denominator = cross(sub(b, a), sub(d, c));
+            if denominator == 0.0 {
+                continue;
+            }
+            let t = cross(sub(c, a), sub(d, c)) / denominator;
+            let u = cross(sub(c, a), sub(b, a)) / denominator;
+            if (0.0..1.0).contains(&t) && (0.0..1.0).contains(&u) {
+                on_subject[i].push((t, crossings.len()));
+                on_clip[j].push((u, crossings.len()));
+                crossings.push(Crossing {
+                    point: [a[0] + t * (b[0] - a[0]), a[1] + t * (b[1] - a[1])],
+                    // The inside of a counter-clockwise polygon is on the left.
+                    entering: denominator < 0.0,
+                });
+            }
+        }
+    }
+
+    if crossings.is_empty() {
+        return if contains(&clip, subject[0]) {
+            vec![subject]
+        } else if contains(&subject, clip[0]) {
+            vec![clip]
+        } else {
+            vec![]
+        };
+    }
+
+    // Each outline as a list of vertices and crossings, and where each
+    // crossing is in it.
+    let outline = |polygon: &[[f32; 2]], edges: &mut [Vec<(f32, usize)>]| {
+        let mut nodes = vec![];
+        let mut positions = vec![0; crossings.len()];
+        for (&vertex, edge) in polygon.iter().zip(edges) {
+            nodes.push(Node::Vertex(vertex));
+            edge.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for &(_, k) in edge.iter() {
+                positions[k] = nodes.len();
+                nodes.push(Node::Crossing(k));
+            }
+        }
+        (nodes, positions)
+    };
+    let subject = outline(&subject, &mut on_subject);
+    let clip = outline(&clip, &mut on_clip);
+
+    let mut visited = vec![false; crossings.len()];
+    let mut polygons = vec![];
+    for start in 0..crossings.len() {
+        if visited[start] || !crossings[start].entering {
+            continue;
+        }
+
+        let mut polygon = vec![];
+        let mut k = start;
+        loop {
+            visited[k] = true;
+            polygon.push(crossings[k].point);
+
+            let (nodes, positions) = if crossings[k].entering {
+                &subject
+            } else {
+                &clip
+            };
+            let mut i = positions[k];
+            k = loop {
+                i = (i + 1) % nodes.len();
+                match nodes[i] {
+                    Node::Vertex(vertex) => polygon.push(vertex),
+                    Node::Crossing(next) => break next,
+                }
+            };
+
+            if k == start || visited[k] {
+                break;
+            }
+        }
+        polygons.push(polygon);
+    }
+    polygons
+}
+
+/// Point where the segment from `p` to `q` crosses the line through `a` and
+/// `b`.
+fn line_intersection(p: [f32; 2], q: [f32; 2], a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    let edge = sub(b, a);
+    let t = cross(edge, sub(a, p)) / cross(edge, sub(q, p));
+    [p[0] + t * (q[0] - p[0]), p[1] + t * (q[1] - p[1])]
+}
+
+/// Even-odd test of `point` against `polygon`.
+fn contains(polygon: &[[f32; 2]], point: [f32; 2]) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let [a, b] = [polygon[i], polygon[(i + 1) % polygon.len()]];
+        if (a[1] > point[1]) != (b[1] > point[1])
+            && point[0] < a[0] + (point[1] - a[1]) * (b[0] - a[0]) / (b[1] - a[1])
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Twice the signed area of `polygon`, positive when it is counter-clockwise.
+fn signed_area(polygon: &[[f32; 2]]) -> f32 {
+    (0..polygon.len())
+        .map(|i| cross(polygon[i], polygon[(i + 1) % polygon.len()]))
+        .sum()
+}
+
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn cross(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+#[test]
+fn clip_test() {
+    let window = Rect::new(0.0, 0.0, 10.0, 8.0);
+
+    assert_eq!(Outcode::of([5.0, 4.0], &window), Outcode::INSIDE);
+    assert_eq!(Outcode::of([-1.0, 9.0], &window).bits(), 0b1001);
+    assert_eq!(
+        Outcode::of([11.0, -1.0], &window),
+        Outcode::RIGHT | Outcode::BOTTOM
+    );
+
+    let close = |a: Option<[[f32; 2]; 2]>, b: Option<[[f32; 2]; 2]>| match (a, b) {
+        (Some(a), Some(b)) => a
+            .iter()
+            .flatten()
+            .zip(b.iter().flatten())
+            .all(|(a, b)| (a - b).abs() < 1e-4),
+        (a, b) => a == b,
+    };
+
+    let cases = [
+        // Inside, accepted straight away.
+        ([1.0, 1.0], [9.0, 7.0], Some([[1.0, 1.0], [9.0, 7.0]])),
+        // Both beyond the left side, rejected straight away.
+        ([-5.0, 1.0], [-1.0, 7.0], None),
+        // Crossing the whole window.
+        ([-5.0, 4.0], [15.0, 4.0], Some([[0.0, 4.0], [10.0, 4.0]])),
+        // Vertical, leaving through the top.
+        ([2.0, 3.0], [2.0, 20.0], Some([[2.0, 3.0], [2.0, 8.0]])),
+        // Passing the top left corner without touching the window, which
+        // takes Cohen–Sutherland a few passes to find out.
+        ([-4.0, 6.0], [2.0, 12.0], None),
+        // Diagonal through two corners.
+        ([-2.0, -2.0], [12.0, 12.0], Some([[0.0, 0.0], [8.0, 8.0]])),
+    ];
+
+    for (p0, p1, expected) in cases {
+        let cs = cohen_sutherland(p0, p1, &window);
+        let lb = liang_barsky(p0, p1, &window);
+        assert!(close(cs.segment, expected), "{p0:?} {p1:?}: {cs:?}");
+        assert!(close(lb.segment, expected), "{p0:?} {p1:?}: {lb:?}");
+    }
+
+    let cs = cohen_sutherland([-4.0, 6.0], [2.0, 12.0], &window);
+    assert_eq!(cs.steps.len(), 2);
+    assert_eq!(cs.steps[0].outcodes, [Outcode::LEFT, Outcode::TOP]);
+    assert_eq!(cs.steps[1].outcodes, [Outcode::TOP, Outcode::TOP]);
+
+    let lb = liang_barsky([-5.0, 4.0], [15.0, 4.0], &window);
+    assert_eq!(lb.p, [-20.0, 20.0, 0.0, 0.0]);
+    assert_eq!(lb.q, [-5.0, 15.0, 4.0, 4.0]);
+    assert_eq!((lb.t0, lb.t1), (0.25, 0.75));
+
+    // Against the same window as a polygon, in both windings, Cyrus–Beck
+    // finds the same parameters as Liang–Barsky.
+    let mut corners = vec![[0.0, 0.0], [10.0, 0.0], [10.0, 8.0], [0.0, 8.0]];
+    for _ in 0..2 {
+        for (p0, p1, expected) in cases {
+            let cb = cyrus_beck(p0, p1, &corners);
+            assert!(close(cb.segment, expected), "{p0:?} {p1:?}: {cb:?}");
+        }
+        corners.reverse();
+    }
+
+    let diamond = [[5.0, 0.0], [10.0, 5.0], [5.0, 10.0], [0.0, 5.0]];
+    let cb = cyrus_beck([-5.0, 5.0], [15.0, 5.0], &diamond);
+    assert_eq!((cb.t_enter, cb.t_leave), (0.25, 0.75));
+    assert!(close(cb.segment, Some([[0.0, 5.0], [10.0, 5.0]])));
+    // Inside the diamond's bounding box but past its corner.
+    assert_eq!(cyrus_beck([0.0, 0.0], [2.0, 1.0], &diamond).segment, None);
+}
+
+#[test]
+fn polygon_clip_test() {
+    let area = |polygon: &[[f32; 2]]| signed_area(polygon).abs() / 2.0;
+
+    let square = [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]];
+    let triangle = [[2.0, -2.0], [6.0, 2.0], [2.0, 6.0]];
+    let clipped = sutherland_hodgman(&triangle, &square);
+    assert_eq!(area(&clipped), 8.0);
+    let mut reversed = square;
+    reversed.reverse();
+    assert_eq!(area(&sutherland_hodgman(&triangle, &reversed)), 8.0);
+    // Cuts the square's top right corner off.
+    let corner = [[0.0, 0.0], [6.0, 0.0], [0.0, 6.0]];
+    assert_eq!(area(&sutherland_hodgman(&corner, &square)), 14.0);
+    assert!(sutherland_hodgman(&[[5.0, 5.0], [6.0, 5.0], [6.0, 6.0]], &square).is_empty());
+
+    // With a convex clip polygon the two agree.
+    let pieces = weiler_atherton(&triangle, &square);
+    assert_eq!(pieces.len(), 1);
+    assert_eq!(area(&pieces[0]), 8.0);
+
+    // A U shape cut across its arms comes apart in two.
+    let u = [
+        [0.0, 0.0],
+        [6.0, 0.0],
+        [6.0, 6.0],
+        [4.0, 6.0],
+        [4.0, 2.0],
+        [2.0, 2.0],
+        [2.0, 6.0],
+        [0.0, 6.0],
+    ];
+    let bar = [[-1.0, 3.0], [7.0, 3.0], [7.0, 5.0], [-1.0, 5.0]];
+    for pieces in [weiler_atherton(&u, &bar), weiler_atherton(&bar, &u)] {
+        assert_eq!(pieces.len(), 2);
+        assert!(pieces.iter().all(|piece| area(piece) == 4.0));
+    }
+    let mut clockwise = u;
+    clockwise.reverse();
+    assert_eq!(weiler_atherton(&bar, &clockwise).len(), 2);
+
+    // No crossings: one inside the other, or apart.
+    let inner = [[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]];
+    assert_eq!(weiler_atherton(&inner, &square), vec![inner.to_vec()]);
+    assert_eq!(weiler_atherton(&square, &inner), vec![inner.to_vec()]);
+    assert!(weiler_atherton(&inner, &bar).is_empty());
+}
@@ -1,59 +1,67 @@
-use crate::opengl;
-use crate::opengl::*;
+use std::f32::consts::FRAC_PI_3;
+
+use crate::camera::Camera;
+use crate::drawable::{Drawable, VertexLayout};
+use crate::opengl::gl;
+use crate::shader::ShaderProgram;
+use crate::transform3d::Transform3D;
+use crate::viewport::Viewport;
 
 pub struct Cube {
     pub vertices: Vec<f32>,
     pub indices: Vec<u32>,
+    pub model: Transform3D,
+    pub camera: Camera,
 }
 
 impl Cube {
-    fn new(length: f32) -> Self {
+    pub fn new(length: f32) -> Self {
         let vertices = vec![
-            -length as f32 / 2.0,
-            -length as f32 / 2.0,
-            length as f32 / 2.0,
+            -length / 2.0,
+            -length / 2.0,
+            length / 2.0,
             1.0,
             1.0,
             1.0,
-            length as f32 / 2.0,
-            -length as f32 / 2.0,
-            length as f32 / 2.0,
+            length / 2.0,
+            -length / 2.0,
+            length / 2.0,
             1.0,
             1.0,
             1.0,
-            length as f32 / 2.0,
-            length as f32 / 2.0,
-            length as f32 / 2.0,
+            length / 2.0,
+            length / 2.0,
+            length / 2.0,
             1.0,
             1.0,
             1.0,
-            -length as f32 / 2.0,
-            length as f32 / 2.0,
-            length as f32 / 2.0,
+            -length / 2.0,
+            length / 2.0,
+            length / 2.0,
             1.0,
             1.0,
             1.0,
-            -length as f32 / 2.0,
-            -length as f32 / 2.0,
-            -length as f32 / 2.0,
+            -length / 2.0,
+            -length / 2.0,
+            -length / 2.0,
             1.0,
             1.0,
             1.0,
-            length as f32 / 2.0,
-            -length as f32 / 2.0,
-            -length as f32 / 2.0,
+            length / 2.0,
+            -length / 2.0,
+            -length / 2.0,
             1.0,
             1.0,
             1.0,
-            length as f32 / 2.0,
-            length as f32 / 2.0,
-            -length as f32 / 2.0,
+            length / 2.0,
+            length / 2.0,
+            -length / 2.0,
             1.0,
             1.0,
             1.0,
-            -length as f32 / 2.0,
-            length as f32 / 2.0,
-            -length as f32 / 2.0,
+            -length / 2.0,
+            length / 2.0,
+            -length / 2.0,
             1.0,
             1.0,
             1.0,
@@ -68,106 +76,58 @@ impl Cube {
             4, 5, 1, 1, 0, 4, // bottom
         ];
 
-        return Self { vertices, indices };
+        Self {
+            vertices,
+            indices,
+            model: Transform3D::rotate_z(FRAC_PI_3)
+                .then(Transform3D::rotate_y(FRAC_PI_3))
+                .then(Transform3D::rotate_x(FRAC_PI_3))
+                .then(Transform3D::uniform_scale(0.5)),
+            camera: Camera::default(),
+        }
+    }
+}
+
+impl Drawable for Cube {
+    fn vertices(&self, _viewport: &Viewport) -> Vec<f32> {
+        self.vertices.clone()
+    }
+
+    fn layout(&self) -> VertexLayout {
+        VertexLayout::position_color(3)
+    }
+
+    fn primitive(&self) -> gl::types::GLenum {
+        gl::TRIANGLES
+    }
+
+    fn indices(&self, _vertex_count: usize) -> Vec<u32> {
+        self.indices.clone()
+    }
+
+    fn vertex_shader(&self) -> &'static [u8] {
+        VERTEX_SHADER_SOURCE
+    }
+
+    fn fragment_shader(&self) -> &'static [u8] {
+        FRAGMENT_SHADER_SOURCE
     }
 
-    pub unsafe fn drawer(renderer: &mut crate::opengl::Renderer) -> () {
-        let vertex_shader =
-            opengl::create_shader(&renderer.gl, gl::VERTEX_SHADER, VERTEX_SHADER_SOURCE);
-        let fragment_shader =
-            create_shader(&renderer.gl, gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SOURCE);
-
-        renderer.program = Some(renderer.gl.CreateProgram());
-
-        renderer
-            .gl
-            .AttachShader(renderer.program.unwrap(), vertex_shader);
-
-        renderer
-            .gl
-            .AttachShader(renderer.program.unwrap(), fragment_shader);
-
-        renderer.gl.LinkProgram(renderer.program.unwrap());
-
-        renderer.gl.UseProgram(renderer.program.unwrap());
-
-        renderer.gl.GenVertexArrays(1, &mut renderer.vao);
-        renderer.gl.BindVertexArray(renderer.vao);
-
-        renderer.gl.GenBuffers(1, &mut renderer.vbo);
-        renderer.gl.BindBuffer(gl::ARRAY_BUFFER, renderer.vbo);
-
-        let cube = Cube::new(0.5);
-
-        let vertex_data = cube.vertices;
-
-        let vertex_indices: Vec<u32> = cube.indices;
-
-        let mut indices: gl::types::GLuint = std::mem::zeroed();
-
-        renderer.gl.GenBuffers(1, &mut indices);
-        renderer.gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, indices);
-        renderer.gl.BufferData(
-            gl::ELEMENT_ARRAY_BUFFER,
-            (vertex_indices.len() * std::mem::size_of::<u32>()) as gl::types::GLsizeiptr,
-            vertex_indices.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        );
-
-        renderer.gl.BufferData(
-            gl::ARRAY_BUFFER,
-            (vertex_data.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
-            vertex_data.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        );
-
-        // POSITION Attribute
-        let pos_attrib = renderer.gl.GetAttribLocation(
-            renderer.program.unwrap(),
-            b"position\0".as_ptr() as *const _,
-        );
-
-        renderer.gl.VertexAttribPointer(
-            pos_attrib as gl::types::GLuint,
-            3,
-            gl::FLOAT,
-            0,
-            6 * std::mem::size_of::<f32>() as gl::types::GLsizei,
-            std::ptr::null(),
-        );
-
-        // COLOR Attribute
-        let color_attrib = renderer
-            .gl
-            .GetAttribLocation(renderer.program.unwrap(), b"color\0".as_ptr() as *const _);
-
-        renderer.gl.VertexAttribPointer(
-            color_attrib as gl::types::GLuint,
-            3,
-            gl::FLOAT,
-            0,
-            6 * std::mem::size_of::<f32>() as gl::types::GLsizei,
-            (3 * std::mem::size_of::<f32>()) as *const _,
-        );
-
-        renderer
-            .gl
-            .EnableVertexAttribArray(pos_attrib as gl::types::GLuint);
-
-        renderer
-            .gl
-            .EnableVertexAttribArray(color_attrib as gl::types::GLuint);
-
-        renderer.gl.ClearColor(0.1, 0.1, 0.1, 0.9);
-
-        renderer.gl.Clear(gl::COLOR_BUFFER_BIT);
-
-        renderer.gl.DrawElements(
-            gl::TRIANGLES,
-            vertex_indices.len() as i32,
-            gl::UNSIGNED_INT,
-            0 as *const _,
-        );
+    unsafe fn set_uniforms(&self, gl: &gl::Gl, program: &ShaderProgram, viewport: &Viewport) {
+        // Follow the window so the cube is not stretched after a resize.
+        let camera = Camera {
+            aspect: viewport.aspect(),
+            ..self.camera
+        };
+
+        for (name, transform) in [
+            (c"model", self.model),
+            (c"view", camera.view()),
+            (c"projection", camera.projection()),
+        ] {
+            let location = program.uniform_location(gl, name);
+            transform.upload(gl, location);
+        }
     }
 }
 
@@ -178,55 +138,12 @@ attribute vec3 position;
 attribute vec3 color;
 varying vec3 v_color;
 
-const float PI = 3.141592654;
-
-float c = cos(-PI/3.0);
-float s = sin(-PI/3.0);
-
-mat4 x_rotate = mat4(
-    1.0, 0.0, 0.0, 0.0,
-    0.0, c, -s, 0.0,
-    0.0, s, c, 0.0,
-    0.0, 0.0, 0.0, 1.0
-); 
-
-mat4 y_rotate = mat4(
-    c, 0.0, s, 0.0,
-    0.0, 1.0, 0.0, 0.0,
-    -s, 0.0, c, 0.0,
-    0.0, 0.0, 0.0, 1.0
-); 
-
-mat4 z_rotate = mat4(
-    c, -s, 0.0, 0.0,
-    s, c, 0.0, 0.0,
-    0.0, 0.0, 1.0, 0.0,
-    0.0, 0.0, 0.0, 1.0
-);
-
-mat4 translation = mat4(
-    1.0, 0.0, 0.0, 0.0,
-    0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 1.0, 0.0,
-    0.5, 0.25, 0.0, 1.0
-);
-
-mat4 scaling = mat4(
-    2.0, 0, 0, 0,
-    0, 0.5, 0, 0,
-    0, 0, 1, 0,
-    0, 0, 0, 1
-);
-
-mat4 uni_scaling = mat4(
-    0.5, 0, 0, 0,
-    0, 0.5, 0, 0,
-    0, 0, 0.5, 0,
-    0, 0, 0, 1
-);
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
 
 void main() {
-    gl_Position = uni_scaling * x_rotate * y_rotate * z_rotate * vec4(position, 1.0);
+    gl_Position = projection * view * model * vec4(position, 1.0);
     v_color = color;
 }
 \0";
@@ -0,0 +1,150 @@
+use crate::framebuffer::{Canvas, Color};
+
+/// Repeating on/off pattern for dashed and dotted lines, measured in pixels
+/// along the major axis of the line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dash {
+    lengths: Vec<u32>,
+    phase: u32,
+}
+
+impl Dash {
+    /// Alternating on and off lengths, starting with on. An odd number of
+    /// lengths is repeated once so on and off still alternate, the same as
+    /// SVG's `stroke-dasharray`.
+    pub fn new(lengths: &[u32]) -> Self {
+        let mut lengths = lengths.to_vec();
+        if lengths.len() % 2 == 1 {
+            lengths.extend_from_within(..);
+        }
+        assert!(
+            lengths.iter().sum::<u32>() > 0,
+            "a dash pattern needs a non-zero length"
+        );
+
+        Self { lengths, phase: 0 }
+    }
+
+    /// OpenGL's `glLineStipple`: bit 0 of `pattern` comes first and every
+    /// bit covers `factor` pixels.
+    pub fn stipple(factor: u32, pattern: u16) -> Self {
+        let mut lengths = vec![0];
+        let mut on = true;
+        for bit in 0..16 {
+            let set = (pattern >> bit) & 1 == 1;
+            if set != on {
+                on = set;
+                lengths.push(0);
+            }
+            *lengths.last_mut().unwrap() += factor;
+        }
+        // Ending on a dash, the pattern wraps straight into the next one.
+        if lengths.len() % 2 == 1 {
+            lengths.push(0);
+        }
+
+        Self::new(&lengths)
+    }
+
+    pub fn lengths(&self) -> &[u32] {
+        &self.lengths
+    }
+
+    /// How far into the pattern the first pixel of a line is.
+    pub fn phase(&self) -> u32 {
+        self.phase
+    }
+
+    pub fn with_phase(self, phase: u32) -> Self {
+        Self {
+            phase: phase % self.period(),
+            ..self
+        }
+    }
+
+    /// Length of one repetition of the pattern.
+    pub fn period(&self) -> u32 {
+        self.lengths.iter().sum()
+    }
+
+    /// Whether the pixel `position` pixels along the line is drawn.
+    pub fn is_on(&self, position: u32) -> bool {
+        let mut position = (position % self.period() + self.phase) % self.period();
+        for (i, &length) in self.lengths.iter().enumerate() {
+            if position < length {
+                return i % 2 == 0;
+            }
+            position -= length;
+        }
+        unreachable!()
+    }
+}
+
+/// Wraps a canvas, letting through only the pixels of a line that fall on
+/// the dashes. Positions along the line are counted along its major axis
+/// from `start`, so both pixels of an anti-aliased column are on or off
+/// together.
+pub struct Dashed<'a, C: Canvas> {
+    canvas: &'a mut C,
+    dash: &'a Dash,
+    start: [i32; 2],
+    steep: bool,
+}
+
+impl<'a, C: Canvas> Dashed<'a, C> {
+    pub fn new(canvas: &'a mut C, dash: &'a Dash, start: [i32; 2], steep: bool) -> Self {
+        Self {
+            canvas,
+            dash,
+            start,
+            steep,
+        }
+    }
+
+    fn is_on(&self, x: i32, y: i32) -> bool {
+        let position = if self.steep {
+            y.abs_diff(self.start[1])
+        } else {
+            x.abs_diff(self.start[0])
+        };
+        self.dash.is_on(position)
+    }
+}
+
+impl<C: Canvas> Canvas for Dashed<'_, C> {
+    fn plot(&mut self, x: i32, y: i32, color: Color) {
+        if self.is_on(x, y) {
+            self.canvas.plot(x, y, color);
+        }
+    }
+
+    fn blend(&mut self, x: i32, y: i32, color: Color, coverage: f32) {
+        if self.is_on(x, y) {
+            self.canvas.blend(x, y, color, coverage);
+        }
+    }
+}
+
+#[test]
+fn dash_test() {
+    let dash = Dash::new(&[3, 2]);
+    let on: Vec<bool> = (0..10).map(|i| dash.is_on(i)).collect();
+    assert_eq!(
+        on,
+        [true, true, true, false, false, true, true, true, false, false]
+    );
+
+    let shifted = dash.clone().with_phase(12);
+    assert_eq!(shifted.phase(), 2);
+    assert!(shifted.is_on(0) && !shifted.is_on(1) && shifted.is_on(3));
+
+    // Odd patterns alternate on the second time round.
+    assert_eq!(Dash::new(&[2]).lengths(), &[2, 2]);
+    assert_eq!(Dash::new(&[4, 1, 1]).lengths(), &[4, 1, 1, 4, 1, 1]);
+
+    assert_eq!(Dash::stipple(1, 0x00ff).lengths(), &[8, 8]);
+    assert_eq!(Dash::stipple(2, 0x0f0f).lengths(), &[8, 8, 8, 8]);
+    // Patterns starting with an off bit start with an empty dash.
+    assert_eq!(Dash::stipple(1, 0xaaaa).lengths()[..3], [0, 1, 1]);
+    assert!((0..100).all(|i| Dash::stipple(3, 0xffff).is_on(i)));
+}
@@ -0,0 +1,115 @@
+use std::ffi::CStr;
+
+use crate::opengl::gl;
+use crate::shader::ShaderProgram;
+use crate::viewport::Viewport;
+
+/// One attribute of an interleaved `f32` vertex, matched by name against the
+/// `attribute` declarations of the vertex shader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexAttribute {
+    pub name: &'static CStr,
+    /// Number of `f32` components.
+    pub size: i32,
+    /// Offset into the vertex, in `f32`s.
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexLayout {
+    /// Size of a whole vertex, in `f32`s.
+    pub stride: usize,
+    pub attributes: Vec<VertexAttribute>,
+}
+
+impl VertexLayout {
+    /// Attributes packed one after the other in the given order.
+    pub fn interleaved(attributes: &[(&'static CStr, i32)]) -> Self {
+        let mut stride = 0;
+        let attributes = attributes
+            .iter()
+            .map(|&(name, size)| {
+                let attribute = VertexAttribute {
+                    name,
+                    size,
+                    offset: stride,
+                };
+                stride += size as usize;
+                attribute
+            })
+            .collect();
+
+        Self { stride, attributes }
+    }
+
+    /// A `position` with `position_size` components followed by an RGB
+    /// `color`, the layout most of the shapes in this crate use.
+    pub fn position_color(position_size: i32) -> Self {
+        Self::interleaved(&[(c"position", position_size), (c"color", 3)])
+    }
+
+    /// Like `position_color` with an alpha channel, for shapes that carry
+    /// per-pixel coverage.
+    pub fn position_color_alpha(position_size: i32) -> Self {
+        Self::interleaved(&[(c"position", position_size), (c"color", 4)])
+    }
+}
+
+/// Anything the `Renderer` can draw. Shapes only describe their geometry and
+/// shaders, the renderer owns all the OpenGL objects.
+pub trait Drawable {
+    /// Interleaved vertex data laid out as described by `layout`. Shapes in
+    /// world coordinates map them to normalized device coordinates through
+    /// `viewport`.
+    fn vertices(&self, viewport: &Viewport) -> Vec<f32>;
+
+    fn layout(&self) -> VertexLayout;
+
+    /// `gl::POINTS`, `gl::LINES`, `gl::TRIANGLES`, ...
+    fn primitive(&self) -> gl::types::GLenum;
+
+    /// Defaults to drawing every vertex once, in order.
+    fn indices(&self, vertex_count: usize) -> Vec<u32> {
+        (0..vertex_count as u32).collect()
+    }
+
+    fn vertex_shader(&self) -> &'static [u8] {
+        VERTEX_SHADER_SOURCE
+    }
+
+    fn fragment_shader(&self) -> &'static [u8] {
+        FRAGMENT_SHADER_SOURCE
+    }
+
+    /// Sets the uniforms the shaders need. Called every frame with `program`
+    /// already in use.
+    ///
+    /// # Safety
+    ///
+    /// The context `gl` was loaded from must be current on the calling thread.
+    unsafe fn set_uniforms(&self, _gl: &gl::Gl, _program: &ShaderProgram, _viewport: &Viewport) {}
+}
+
+/// `color` is declared as a `vec4` so the same shaders serve RGB and RGBA
+/// layouts, OpenGL fills in an alpha of 1 for attributes with three
+/// components.
+pub const VERTEX_SHADER_SOURCE: &[u8] = b"
+#version 100
+precision mediump float;
+attribute vec3 position;
+attribute vec4 color;
+varying vec4 v_color;
+void main() {
+    gl_Position = vec4(position, 1.0);
+    v_color = color;
+}
+\0";
+
+pub const FRAGMENT_SHADER_SOURCE: &[u8] = b"
+#version 100
+precision mediump float;
+varying vec4 v_color;
+void main() {
+    gl_FragColor = v_color;
+}
+\0";
@@ -1,36 +1,176 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::arc::Arc;
+use crate::drawable::{Drawable, VertexLayout};
+use crate::fill::symmetric_spans;
+use crate::framebuffer::{Canvas, Color, Coverage, Points, Span, WHITE};
+use crate::line::{Line, LineAlgorithm};
+use crate::opengl::gl;
+use crate::transform::transform_points;
+use crate::viewport::Viewport;
+
+/// How steep the rotated outline gets, as `|∂F/∂v| / |∂F/∂u|`, before its
+/// columns hand over to rows. Below 1 so the two overlap a little.
+const HANDOVER: f64 = 0.9;
+
 pub struct Ellipse {
-    points: Vec<[i32; 2]>,
+    points: Vec<[f32; 2]>,
+    coverage: Vec<f32>,
     centre: [i32; 2],
     a: i32,
     b: i32,
+    rotation: f32,
+    filled: bool,
+    antialiased: bool,
 }
 
 impl Ellipse {
+    /// Every constructor panics if `a` or `b` is negative.
     pub fn new(centre: [i32; 2], a: i32, b: i32) -> Self {
+        Self::with_style(centre, a, b, false, false)
+    }
+
+    /// A solid ellipse, made of the same pixels as the outline and everything
+    /// between them.
+    pub fn filled(centre: [i32; 2], a: i32, b: i32) -> Self {
+        Self::with_style(centre, a, b, true, false)
+    }
+
+    /// An outline with Wu's anti-aliasing, its coverage of every pixel in
+    /// `coverage`.
+    pub fn antialiased(centre: [i32; 2], a: i32, b: i32) -> Self {
+        Self::with_style(centre, a, b, false, true)
+    }
+
+    fn with_style(centre: [i32; 2], a: i32, b: i32, filled: bool, antialiased: bool) -> Self {
+        assert!(a >= 0 && b >= 0, "an ellipse needs semi-axes of at least 0");
         let mut ellipse = Self {
             points: vec![],
+            coverage: vec![],
             centre,
             a,
             b,
+            rotation: 0.0,
+            filled,
+            antialiased,
         };
+        ellipse.collect_points();
+        ellipse
+    }
+
+    /// Turns the ellipse `angle` radians counterclockwise about its centre.
+    /// The rotated outline is rasterized straight from its equation rather
+    /// than by rotating the axis-aligned pixels, so it has no gaps at any
+    /// angle.
+    pub fn with_rotation(mut self, angle: f32) -> Self {
+        self.rotation = angle;
+        self.collect_points();
+        self
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    fn collect_points(&mut self) {
+        let mut points = Points::default();
+        self.rasterize(&mut points, WHITE);
+        self.points = points.points;
+        self.coverage = points.coverage;
+    }
+
+    /// Plots the ellipse outline, or the whole ellipse if it is filled,
+    /// straight into `canvas`. Anti-aliased, every pixel is blended once with
+    /// the most coverage any quadrant, column or row gave it.
+    pub fn rasterize(&self, canvas: &mut impl Canvas, color: Color) {
+        if self.filled {
+            for span in self.spans() {
+                canvas.fill_span(span, color);
+            }
+        } else if self.antialiased && !self.is_flat() {
+            let [cx, cy] = self.centre;
+            let mut coverage = Coverage::default();
+            if self.rotation == 0.0 {
+                self.generate_wu_ellipse(|x, y, c| {
+                    for [sx, sy] in [[1, 1], [-1, 1], [1, -1], [-1, -1]] {
+                        coverage.blend(cx + sx * x, cy + sy * y, color, c);
+                    }
+                });
+            } else {
+                self.rotated_crossings(|u, v, transpose| {
+                    let below = v.floor();
+                    for (w, c) in [(below, 1.0 - (v - below)), (below + 1.0, v - below)] {
+                        let [x, y] = if transpose {
+                            [w as i32, u]
+                        } else {
+                            [u, w as i32]
+                        };
+                        coverage.blend(cx + x, cy + y, color, c as f32);
+                    }
+                });
+            }
+            coverage.rasterize(canvas, color);
+        } else {
+            self.outline(canvas, color);
+        }
+    }
+
+    /// With either semi-axis zero the ellipse is nothing but its long axis,
+    /// drawn as a line however it is rotated, filled or anti-aliased.
+    fn is_flat(&self) -> bool {
+        self.a == 0 || self.b == 0
+    }
+
+    fn outline(&self, canvas: &mut impl Canvas, color: Color) {
+        if self.rotation == 0.0 && !self.is_flat() {
+            self.generate_midpoint_ellipse(|x, y| self.push_symmetric_points(canvas, color, x, y));
+        } else {
+            let [cx, cy] = self.centre;
+            self.generate_rotated_ellipse(|x, y| canvas.plot(cx + x, cy + y, color));
+        }
+    }
 
-        ellipse.generate_midpoint_ellipse();
+    /// The filled ellipse as one span per row, bottom to top, each reaching
+    /// the outermost outline point on its row.
+    pub fn spans(&self) -> Vec<Span> {
+        if self.rotation != 0.0 || self.is_flat() {
+            let [cx, cy] = self.centre;
+            let mut rows: BTreeMap<i32, [i32; 2]> = BTreeMap::new();
+            self.generate_rotated_ellipse(|x, y| {
+                let row = rows.entry(y).or_insert([x, x]);
+                *row = [row[0].min(x), row[1].max(x)];
+            });
+            return rows
+                .into_iter()
+                .map(|(y, [x0, x1])| Span {
+                    y: cy + y,
+                    x0: cx + x0,
+                    x1: cx + x1,
+                })
+                .collect();
+        }
 
-        return ellipse;
+        let mut half_widths = vec![0; self.b as usize + 1];
+        self.generate_midpoint_ellipse(|x, y| {
+            half_widths[y as usize] = half_widths[y as usize].max(x);
+        });
+        symmetric_spans(self.centre, &half_widths)
     }
 
-    fn generate_midpoint_ellipse(&mut self) {
+    /// Walks the first quadrant, from the top of the ellipse down to the
+    /// x axis, calling `visit` with every point's offset from the centre.
+    fn generate_midpoint_ellipse(&self, mut visit: impl FnMut(i32, i32)) {
         let mut x = 0;
         let mut y = self.b;
 
         let a2 = (self.a * self.a) as f32;
         let b2 = (self.b * self.b) as f32;
-        let mut d = (b2 - a2 * self.b as f32 + (a2 / 4.0)) as f32;
+        let mut d = b2 - a2 * self.b as f32 + (a2 / 4.0);
 
-        self.push_symmetric_points(x, y);
+        visit(x, y);
 
         // Region 1
-        while (a2 as f32 * (y as f32 - 0.5)) > (b2 as f32 * (x + 1) as f32) {
+        while (a2 * (y as f32 - 0.5)) > (b2 * (x + 1) as f32) {
             if d < 0.0 {
                 // Select E
                 d += b2 * (2 * x + 3) as f32;
@@ -41,13 +181,12 @@ impl Ellipse {
             }
             x += 1;
 
-            self.push_symmetric_points(x, y);
+            visit(x, y);
         }
 
         // Region 2
-        d = b2 as f32 * (x as f32 + 0.5) * (x as f32 + 0.5)
-            + a2 as f32 * (y - 1) as f32 * (y - 1) as f32
-            - a2 as f32 * b2 as f32;
+        d = b2 * (x as f32 + 0.5) * (x as f32 + 0.5) + a2 * (y - 1) as f32 * (y - 1) as f32
+            - a2 * b2;
         while y > 0 {
             if d < 0.0 {
                 // Select SE
@@ -59,47 +198,375 @@ impl Ellipse {
             }
             y -= 1;
 
-            self.push_symmetric_points(x, y);
+            visit(x, y);
+        }
+    }
+
+    /// Rasterizes the rotated ellipse straight from its implicit equation,
+    /// `F(x, y) = Ax² + Bxy + Cy² - a²b²`, calling `visit` with every pixel's
+    /// offset from the centre.
+    ///
+    /// Where the curve runs closer to horizontal, `F = 0` is solved as a
+    /// quadratic in y at every whole x, and each column gets the pixel each
+    /// root rounds to; where it runs closer to vertical, each row does the
+    /// same with x. Solving for the crossings of every column and row, rather
+    /// than stepping from pixel to pixel, keeps the two sides of a thin
+    /// ellipse apart even where they run through the same pixels.
+    fn generate_rotated_ellipse(&self, mut visit: impl FnMut(i32, i32)) {
+        let (sin, cos) = (self.rotation as f64).sin_cos();
+
+        if self.is_flat() {
+            let r = self.a.max(self.b) as f64;
+            let end = [(r * cos) as f32, (r * sin) as f32];
+            let end = if self.a > 0 { end } else { [-end[1], end[0]] };
+            let mut points = vec![];
+            Line::new([-end[0], -end[1]], end, LineAlgorithm::Bresenham)
+                .rasterize(&mut points, WHITE);
+            for [x, y] in points {
+                visit(x as i32, y as i32);
+            }
+            return;
+        }
+
+        let mut pixels = BTreeSet::new();
+        self.rotated_crossings(|u, v, transpose| {
+            let v = v.round() as i32;
+            pixels.insert(if transpose { [v, u] } else { [u, v] });
+        });
+        for [x, y] in pixels {
+            visit(x, y);
+        }
+    }
+
+    /// Where the rotated ellipse crosses every column, at whole `u` and
+    /// exact `v`, along the stretches it runs closer to horizontal, and the
+    /// same for every row where it runs closer to vertical, flagged by
+    /// `transpose` with `u` then being `y`.
+    ///
+    /// Columns are kept while `|∂F/∂v| >= HANDOVER * |∂F/∂u|`, up to about
+    /// 48° rather than exactly 45°, and rows likewise. The overlap costs the
+    /// odd pixel that both pick, plotted once, but stops a gap opening where
+    /// columns hand over to rows on a tight bend, where rounding the two
+    /// crossings either side of 45° can land them two pixels apart.
+    fn rotated_crossings(&self, mut visit: impl FnMut(i32, f64, bool)) {
+        let (sin, cos) = (self.rotation as f64).sin_cos();
+        let a2 = (self.a * self.a) as f64;
+        let b2 = (self.b * self.b) as f64;
+        let coef_a = b2 * cos * cos + a2 * sin * sin;
+        let coef_b = 2.0 * sin * cos * (b2 - a2);
+        let coef_c = b2 * sin * sin + a2 * cos * cos;
+
+        // The equation is the same with x and y swapped but A and C swapped
+        // too, so rows are columns of the mirrored ellipse.
+        for (across, along, transpose) in [(coef_a, coef_c, false), (coef_c, coef_a, true)] {
+            let extent = (a2 * b2 * 4.0 * along / (4.0 * across * along - coef_b * coef_b)).sqrt();
+            for u in -extent.ceil() as i32..=extent.ceil() as i32 {
+                let u = u as f64;
+                let discriminant = (coef_b * u).powi(2) - 4.0 * along * (across * u * u - a2 * b2);
+                if discriminant < 0.0 {
+                    continue;
+                }
+                for v in [-1.0, 1.0]
+                    .map(|root| (-coef_b * u + root * discriminant.sqrt()) / (2.0 * along))
+                {
+                    let gradient_u = 2.0 * across * u + coef_b * v;
+                    let gradient_v = coef_b * u + 2.0 * along * v;
+                    if gradient_v.abs() >= HANDOVER * gradient_u.abs() {
+                        visit(u as i32, v, transpose);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The anti-aliased take on the first quadrant: every column until the
+    /// curve gets steeper than 45°, and every row from there down to the x
+    /// axis, with the ellipse's exact position on each shared between the two
+    /// candidate pixels either side of it, each getting more the closer the
+    /// ellipse passes.
+    fn generate_wu_ellipse(&self, mut visit: impl FnMut(i32, i32, f32)) {
+        let a2 = (self.a * self.a) as f32;
+        let b2 = (self.b * self.b) as f32;
+        let diagonal = (a2 + b2).sqrt();
+
+        let mut x = 0;
+        while x as f32 <= a2 / diagonal {
+            let y = (b2 * (1.0 - (x * x) as f32 / a2)).sqrt();
+            let below = y.floor();
+            visit(x, below as i32, 1.0 - (y - below));
+            visit(x, below as i32 + 1, y - below);
+            x += 1;
+        }
+
+        let mut y = 0;
+        while y as f32 <= b2 / diagonal {
+            let x = (a2 * (1.0 - (y * y) as f32 / b2)).sqrt();
+            let left = x.floor();
+            visit(left as i32, y, 1.0 - (x - left));
+            visit(left as i32 + 1, y, x - left);
+            y += 1;
         }
     }
 
-    fn push_symmetric_points(&mut self, x: i32, y: i32) {
-        self.points.push([self.centre[0] + x, self.centre[1] + y]);
-        self.points.push([self.centre[0] - x, self.centre[1] + y]);
-        self.points.push([self.centre[0] + x, self.centre[1] - y]);
-        self.points.push([self.centre[0] - x, self.centre[1] - y]);
+    fn push_symmetric_points(&self, canvas: &mut impl Canvas, color: Color, x: i32, y: i32) {
+        let [cx, cy] = self.centre;
+        canvas.plot(cx + x, cy + y, color);
+        canvas.plot(cx - x, cy + y, color);
+        canvas.plot(cx + x, cy - y, color);
+        canvas.plot(cx - x, cy - y, color);
     }
 
-    pub fn get_normalized_coordinate(&self) -> Vec<f32> {
-        return self
-            .points
+    /// The part of the ellipse from `start` to `end`, filled if the ellipse is.
+    /// Arcs are always cut from the aliased outline.
+    pub fn arc(&self, start: f32, end: f32) -> Arc {
+        let mut outline = vec![];
+        self.outline(&mut outline, WHITE);
+        Arc::new(self.centre, &outline, self.spans(), start, end).with_filled(self.filled)
+    }
+
+    pub fn points(&self) -> &[[f32; 2]] {
+        &self.points
+    }
+
+    /// How much of each pixel in `points` the outline covers, 1 unless it
+    /// is anti-aliased.
+    pub fn coverage(&self) -> &[f32] {
+        &self.coverage
+    }
+
+    /// Vertices laid out as `VertexLayout::position_color_alpha(3)`, the same
+    /// as `Line::get_normalized_coordinate`.
+    pub fn get_normalized_coordinate(&self, viewport: &Viewport) -> Vec<f32> {
+        let window_to_ndc = viewport.window_to_ndc();
+        self.points
             .iter()
-            .flat_map(|item| {
+            .zip(&self.coverage)
+            .flat_map(|(&item, &coverage)| {
+                let [x, y] = window_to_ndc.apply(item);
                 [
-                    (item[0] as f32 / 800.0) - 1.0,
-                    (item[1] as f32 / 600.0) - 1.0,
-                    0.0, // Z
+                    x, y, 0.0, // Z
                     1.0, // R
                     1.0, // G
                     1.0, // B
+                    coverage,
                 ]
             })
-            .collect();
+            .collect()
     }
 
-    pub fn get_2d_normalized_coordinate(&self) -> Vec<f32> {
-        return self
-            .points
+    pub fn get_2d_normalized_coordinate(&self, viewport: &Viewport) -> Vec<f32> {
+        let window_to_ndc = viewport.window_to_ndc();
+        self.points
             .iter()
-            .flat_map(|item| {
+            .flat_map(|&item| {
+                let [x, y] = window_to_ndc.apply(item);
                 [
-                    (item[0] as f32 / 800.0) - 1.0,
-                    (item[1] as f32 / 600.0) - 1.0,
-                    1.0, // R
+                    x, y, 1.0, // R
                     1.0, // G
                     1.0, // B
                 ]
             })
-            .collect();
+            .collect()
+    }
+
+    /// Applies a row-major 2D homogeneous matrix, or a `Transform2D`, to the
+    /// rasterized points.
+    /// The outline given to `rasterize` is left untouched.
+    pub fn transform(&mut self, matrix: impl Into<[[f32; 3]; 3]>) {
+        transform_points(&mut self.points, matrix.into());
+    }
+}
+
+impl Drawable for Ellipse {
+    fn vertices(&self, viewport: &Viewport) -> Vec<f32> {
+        self.get_normalized_coordinate(viewport)
+    }
+
+    fn layout(&self) -> VertexLayout {
+        VertexLayout::position_color_alpha(3)
+    }
+
+    fn primitive(&self) -> gl::types::GLenum {
+        gl::POINTS
+    }
+}
+
+#[test]
+fn filled_ellipse_test() {
+    use crate::fill::{boundary_fill, Connectivity};
+    use crate::framebuffer::Framebuffer;
+
+    for [a, b] in [[0, 0], [1, 1], [3, 1], [20, 6], [6, 20], [23, 17]] {
+        let filled = Ellipse::filled([24, 24], a, b);
+        assert_eq!(filled.spans().len(), 2 * b as usize + 1);
+
+        let mut expected = Framebuffer::new(48, 48);
+        Ellipse::new([24, 24], a, b).rasterize(&mut expected, WHITE);
+        if a > 0 && b > 0 {
+            boundary_fill(&mut expected, [24, 24], WHITE, WHITE, Connectivity::Four);
+        }
+        let mut actual = Framebuffer::new(48, 48);
+        filled.rasterize(&mut actual, WHITE);
+        assert_eq!(actual, expected, "{a}x{b}");
+    }
+}
+
+#[test]
+fn flat_ellipse_test() {
+    use std::collections::BTreeSet;
+
+    let pixels = |ellipse: Ellipse| -> (usize, BTreeSet<[i32; 2]>) {
+        let points = ellipse.points();
+        (
+            points.len(),
+            points.iter().map(|&[x, y]| [x as i32, y as i32]).collect(),
+        )
+    };
+
+    // Only the long axis, the same however the ellipse is drawn and whether
+    // it is rotated a hair or not at all.
+    for [a, b] in [[5, 0], [0, 5], [0, 0]] {
+        let axis: BTreeSet<_> = (-5..=5).map(|i| [i * a / 5, i * b / 5]).collect();
+        for angle in [0.0, 0.001] {
+            for ellipse in [
+                Ellipse::new([0, 0], a, b),
+                Ellipse::filled([0, 0], a, b),
+                Ellipse::antialiased([0, 0], a, b),
+            ] {
+                let (count, unique) = pixels(ellipse.with_rotation(angle));
+                assert_eq!(unique, axis, "{a}x{b} at {angle}");
+                assert_eq!(count, axis.len(), "{a}x{b} at {angle}");
+            }
+        }
     }
 }
+
+#[test]
+fn rotated_ellipse_test() {
+    use std::collections::HashSet;
+
+    for [a, b] in [
+        [1, 1],
+        [3, 1],
+        [9, 4],
+        [30, 12],
+        [17, 40],
+        [25, 25],
+        [60, 2],
+    ] {
+        // Ends sharper than a pixel close up into a single line.
+        let blunt = b * b >= a && a * a >= b;
+        for degrees in (0..180).step_by(7).skip(1) {
+            let angle = (degrees as f32).to_radians();
+            let ellipse = Ellipse::new([0, 0], a, b).with_rotation(angle);
+            let pixels: Vec<[i32; 2]> = ellipse
+                .points()
+                .iter()
+                .map(|&[x, y]| [x as i32, y as i32])
+                .collect();
+
+            // Each pixel once, all joined up with no loose ends.
+            let unique: HashSet<_> = pixels.iter().copied().collect();
+            assert_eq!(unique.len(), pixels.len(), "{a}x{b} at {degrees}°");
+            let neighbours = |[x, y]: [i32; 2]| {
+                (-1..=1)
+                    .flat_map(move |dx| (-1..=1).map(move |dy| [x + dx, y + dy]))
+                    .filter(|&q| q != [x, y] && unique.contains(&q))
+                    .collect::<Vec<_>>()
+            };
+            let mut reached = HashSet::from([pixels[0]]);
+            let mut stack = vec![pixels[0]];
+            while let Some(p) = stack.pop() {
+                assert!(
+                    !blunt || neighbours(p).len() >= 2,
+                    "{a}x{b} at {degrees}°: {p:?}"
+                );
+                stack.extend(neighbours(p).into_iter().filter(|&q| reached.insert(q)));
+            }
+            assert_eq!(reached.len(), pixels.len(), "{a}x{b} at {degrees}°");
+
+            // The true curve passes through every pixel.
+            let (sin, cos) = angle.sin_cos();
+            let f = |x: f32, y: f32| {
+                let [u, v] = [x * cos + y * sin, y * cos - x * sin];
+                (u / a as f32).powi(2) + (v / b as f32).powi(2) - 1.0
+            };
+            for &[x, y] in &pixels {
+                let samples: Vec<f32> = (0..25)
+                    .map(|i| {
+                        f(
+                            x as f32 + (i % 5) as f32 / 4.0 - 0.5,
+                            y as f32 + (i / 5) as f32 / 4.0 - 0.5,
+                        )
+                    })
+                    .collect();
+                let inside = samples.iter().any(|&f| f <= 0.0);
+                let outside = samples.iter().any(|&f| f >= 0.0);
+                assert!(inside && outside, "{a}x{b} at {degrees}°: {x},{y}");
+            }
+
+            // Filled, the outline and everything between it.
+            let filled = Ellipse::filled([0, 0], a, b).with_rotation(angle);
+            let filled: HashSet<_> = filled
+                .points()
+                .iter()
+                .map(|&[x, y]| [x as i32, y as i32])
+                .collect();
+            assert!(unique.iter().all(|p| filled.contains(p)));
+        }
+    }
+}
+
+#[test]
+fn antialiased_ellipse_test() {
+    use std::collections::HashMap;
+
+    for [a, b] in [[1, 1], [9, 4], [30, 12], [17, 40], [60, 2]] {
+        for degrees in [0, 20, 45, 90, 133] {
+            let angle = (degrees as f32).to_radians();
+            let ellipse = Ellipse::antialiased([0, 0], a, b).with_rotation(angle);
+            assert_eq!(ellipse.coverage().len(), ellipse.points().len());
+            assert!(ellipse.coverage().iter().all(|&c| c > 0.0 && c <= 1.0));
+
+            // Each pixel once, with the true curve less than a pixel away.
+            let coverage: HashMap<[i32; 2], f32> = ellipse
+                .points()
+                .iter()
+                .map(|&[x, y]| [x as i32, y as i32])
+                .zip(ellipse.coverage().iter().copied())
+                .collect();
+            assert_eq!(coverage.len(), ellipse.points().len());
+            let (sin, cos) = angle.sin_cos();
+            let f = |x: f32, y: f32| {
+                let [u, v] = [x * cos + y * sin, y * cos - x * sin];
+                (u / a as f32).powi(2) + (v / b as f32).powi(2) - 1.0
+            };
+            for &[x, y] in coverage.keys() {
+                let samples: Vec<f32> = (0..25)
+                    .map(|i| {
+                        f(
+                            x as f32 + (i % 5) as f32 / 2.0 - 1.0,
+                            y as f32 + (i / 5) as f32 / 2.0 - 1.0,
+                        )
+                    })
+                    .collect();
+                let inside = samples.iter().any(|&f| f <= 0.0);
+                let outside = samples.iter().any(|&f| f >= 0.0);
+                assert!(inside && outside, "{a}x{b} at {degrees}°: {x},{y}");
+            }
+
+            // The ends of the axes fall right on pixel centres.
+            if degrees == 0 {
+                for pixel in [[a, 0], [-a, 0], [0, b], [0, -b]] {
+                    assert_eq!(coverage.get(&pixel), Some(&1.0), "{a}x{b}: {pixel:?}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "semi-axes of at least 0")]
+fn negative_semi_axis_test() {
+    Ellipse::filled([0, 0], 5, -3);
+}
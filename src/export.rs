@@ -0,0 +1,40 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::framebuffer::Framebuffer;
+
+impl Framebuffer {
+    /// Writes the framebuffer as an 8-bit RGBA PNG.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), png::EncodingError> {
+        let file = BufWriter::new(File::create(path)?);
+
+        let mut encoder = png::Encoder::new(file, self.width(), self.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_rgba_bytes())
+    }
+}
+
+#[test]
+fn save_png_test() {
+    use crate::circle::Circle;
+    use crate::framebuffer::WHITE;
+
+    let mut framebuffer = Framebuffer::new(64, 48);
+    Circle::new([32, 24], 20).rasterize(&mut framebuffer, WHITE);
+
+    let path = std::env::temp_dir().join("biopix_save_png_test.png");
+    framebuffer.save_png(&path).unwrap();
+
+    let decoder = png::Decoder::new(File::open(&path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!((info.width, info.height), (64, 48));
+    assert_eq!(data, framebuffer.to_rgba_bytes());
+}
@@ -0,0 +1,492 @@
+use std::collections::BTreeMap;
+
+use crate::drawable::{Drawable, VertexLayout};
+use crate::framebuffer::{Canvas, Color, Framebuffer, Span};
+use crate::opengl::gl;
+use crate::viewport::Viewport;
+
+/// Which points count as inside a self-intersecting polygon.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FillRule {
+    /// Inside where a ray to infinity crosses the outline an odd number of
+    /// times.
+    #[default]
+    EvenOdd,
+    /// Inside where the outline winds around the point at all.
+    NonZero,
+}
+
+/// One non-horizontal polygon edge as the scan-line algorithm tracks it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Edge {
+    /// First row past the top of the edge.
+    pub y_end: i32,
+    /// Where the edge crosses the current row.
+    pub x: f32,
+    /// How far `x` moves from one row to the next.
+    pub dx_dy: f32,
+    /// +1 for edges going up, -1 for edges going down.
+    pub winding: i32,
+}
+
+/// Edges keyed by the first row they cross, that is the rows of pixel centres
+/// from `ceil(ymin)` up to but not including `ceil(ymax)`. Horizontal edges
+/// and edges between two rows cross none and are left out.
+pub fn edge_table(vertices: &[[f32; 2]]) -> BTreeMap<i32, Vec<Edge>> {
+    let mut table: BTreeMap<i32, Vec<Edge>> = BTreeMap::new();
+    for i in 0..vertices.len() {
+        let [a, b] = [vertices[i], vertices[(i + 1) % vertices.len()]];
+        let (low, high, winding) = if a[1] < b[1] { (a, b, 1) } else { (b, a, -1) };
+
+        let y_start = low[1].ceil() as i32;
+        let y_end = high[1].ceil() as i32;
+        if y_start == y_end {
+            continue;
+        }
+
+        let dx_dy = (high[0] - low[0]) / (high[1] - low[1]);
+        table.entry(y_start).or_default().push(Edge {
+            y_end,
+            x: low[0] + (y_start as f32 - low[1]) * dx_dy,
+            dx_dy,
+            winding,
+        });
+    }
+    table
+}
+
+/// Fills the polygon row by row: edges move from the edge table into the
+/// active edge table on the row they start, and the spans on each row run
+/// between the crossings of the active edges, paired up by `rule`.
+///
+/// A pixel is filled when its centre is inside, with centres on the left and
+/// bottom of the outline counted in and those on the right and top out, so
+/// polygons sharing an edge never both fill a pixel on it.
+pub fn scanline_fill(vertices: &[[f32; 2]], rule: FillRule) -> Vec<Span> {
+    let mut table = edge_table(vertices);
+    let mut active: Vec<Edge> = vec![];
+    let mut spans = vec![];
+
+    let Some(mut y) = table.keys().next().copied() else {
+        return spans;
+    };
+    loop {
+        if let Some(edges) = table.remove(&y) {
+            active.extend(edges);
+        }
+        active.retain(|edge| edge.y_end > y);
+        if active.is_empty() {
+            match table.keys().next() {
+                Some(&next) => {
+                    y = next;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        active.sort_by(|a, b| a.x.total_cmp(&b.x));
+        let mut push = |left: f32, right: f32| {
+            let (x0, x1) = (left.ceil() as i32, right.ceil() as i32 - 1);
+            if x0 <= x1 {
+                spans.push(Span { y, x0, x1 });
+            }
+        };
+        match rule {
+            FillRule::EvenOdd => {
+                for pair in active.chunks_exact(2) {
+                    push(pair[0].x, pair[1].x);
+                }
+            }
+            FillRule::NonZero => {
+                let mut winding = 0;
+                let mut left = 0.0;
+                for edge in &active {
+                    if winding == 0 {
+                        left = edge.x;
+                    }
+                    winding += edge.winding;
+                    if winding == 0 {
+                        push(left, edge.x);
+                    }
+                }
+            }
+        }
+
+        for edge in &mut active {
+            edge.x += edge.dx_dy;
+        }
+        y += 1;
+    }
+    spans
+}
+
+/// A polygon, which may be concave or self-intersecting, filled on the CPU.
+pub struct FilledPolygon {
+    vertices: Vec<[f32; 2]>,
+    rule: FillRule,
+    spans: Vec<Span>,
+}
+
+impl FilledPolygon {
+    pub fn new(vertices: &[[f32; 2]], rule: FillRule) -> Self {
+        Self {
+            vertices: vertices.to_vec(),
+            rule,
+            spans: scanline_fill(vertices, rule),
+        }
+    }
+
+    pub fn vertices(&self) -> &[[f32; 2]] {
+        &self.vertices
+    }
+
+    pub fn rule(&self) -> FillRule {
+        self.rule
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    pub fn rasterize(&self, canvas: &mut impl Canvas, color: Color) {
+        for &span in &self.spans {
+            canvas.fill_span(span, color);
+        }
+    }
+}
+
+impl Drawable for FilledPolygon {
+    fn vertices(&self, viewport: &Viewport) -> Vec<f32> {
+        span_vertices(&self.spans, viewport)
+    }
+
+    fn layout(&self) -> VertexLayout {
+        VertexLayout::position_color(3)
+    }
+
+    fn primitive(&self) -> gl::types::GLenum {
+        gl::TRIANGLES
+    }
+}
+
+/// Spans of a shape symmetric about the horizontal and vertical lines through
+/// `centre`, given how far each row reaches either side of it, starting with
+/// the centre row and going up. Rows are returned bottom to top.
+pub fn symmetric_spans(centre: [i32; 2], half_widths: &[i32]) -> Vec<Span> {
+    let [cx, cy] = centre;
+    let rows = half_widths.len() as i32;
+    (1 - rows..rows)
+        .map(|dy| {
+            let half_width = half_widths[dy.unsigned_abs() as usize];
+            Span {
+                y: cy + dy,
+                x0: cx - half_width,
+                x1: cx + half_width,
+            }
+        })
+        .collect()
+}
+
+/// Two triangles for every span covering its pixels, laid out as
+/// `VertexLayout::position_color(3)`, to draw CPU filled shapes with
+/// `gl::TRIANGLES`.
+pub fn span_vertices(spans: &[Span], viewport: &Viewport) -> Vec<f32> {
+    let window_to_ndc = viewport.window_to_ndc();
+    spans
+        .iter()
+        .flat_map(|span| {
+            let [left, right] = [span.x0 as f32 - 0.5, span.x1 as f32 + 0.5];
+            let [bottom, top] = [span.y as f32 - 0.5, span.y as f32 + 0.5];
+            [
+                [left, bottom],
+                [right, bottom],
+                [right, top],
+                [left, bottom],
+                [right, top],
+                [left, top],
+            ]
+        })
+        .flat_map(|item| {
+            let [x, y] = window_to_ndc.apply(item);
+            [
+                x, y, 0.0, // Z
+                1.0, // R
+                1.0, // G
+                1.0, // B
+            ]
+        })
+        .collect()
+}
+
+/// Which neighbours a seed fill spreads to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Left, right, up and down. Stays inside outlines drawn by the line,
+    /// circle and ellipse rasterizers, which only touch diagonally in places.
+    #[default]
+    Four,
+    /// The diagonals as well. Needs an outline without diagonal gaps.
+    Eight,
+}
+
+impl Connectivity {
+    fn neighbours(self) -> &'static [[i32; 2]] {
+        match self {
+            Connectivity::Four => &[[1, 0], [-1, 0], [0, 1], [0, -1]],
+            Connectivity::Eight => &[
+                [1, 0],
+                [-1, 0],
+                [0, 1],
+                [0, -1],
+                [1, 1],
+                [-1, 1],
+                [1, -1],
+                [-1, -1],
+            ],
+        }
+    }
+}
+
+/// The pixels a seed fill spreads through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeedRegion {
+    /// Everything up to an outline of this colour.
+    Boundary(Color),
+    /// Everything the colour the seed pixel had.
+    Flood,
+}
+
+/// Fills outwards from `seed` up to pixels of the `boundary` colour.
+pub fn boundary_fill(
+    framebuffer: &mut Framebuffer,
+    seed: [i32; 2],
+    boundary: Color,
+    color: Color,
+    connectivity: Connectivity,
+) {
+    seed_fill(
+        framebuffer,
+        seed,
+        color,
+        SeedRegion::Boundary(boundary),
+        connectivity,
+    );
+}
+
+/// Recolours the pixels connected to `seed` that have the same colour it had.
+pub fn flood_fill(
+    framebuffer: &mut Framebuffer,
+    seed: [i32; 2],
+    color: Color,
+    connectivity: Connectivity,
+) {
+    seed_fill(framebuffer, seed, color, SeedRegion::Flood, connectivity);
+}
+
+/// The textbook seed fill, one pixel at a time: fill a pixel in the region,
+/// then try each of its neighbours. The neighbours go on a stack rather than
+/// into recursive calls, which would overflow on regions of any size.
+pub fn seed_fill(
+    framebuffer: &mut Framebuffer,
+    seed: [i32; 2],
+    color: Color,
+    region: SeedRegion,
+    connectivity: Connectivity,
+) {
+    let Some(target) = seed_color(framebuffer, seed, color, region) else {
+        return;
+    };
+    let inside = |framebuffer: &Framebuffer, x: i32, y: i32| {
+        in_region(framebuffer.get(x, y), target, color, region)
+    };
+
+    let mut stack = vec![seed];
+    while let Some([x, y]) = stack.pop() {
+        if inside(framebuffer, x, y) {
+            framebuffer.plot(x, y, color);
+            stack.extend(
+                connectivity
+                    .neighbours()
+                    .iter()
+                    .map(|[dx, dy]| [x + dx, y + dy]),
+            );
+        }
+    }
+}
+
+/// Seed fill a whole run at a time: fill the run of region pixels through the
+/// seed, then push one seed for every run touching it on the rows above and
+/// below. The stack holds runs instead of pixels, so it stays short.
+pub fn scanline_seed_fill(
+    framebuffer: &mut Framebuffer,
+    seed: [i32; 2],
+    color: Color,
+    region: SeedRegion,
+    connectivity: Connectivity,
+) {
+    let Some(target) = seed_color(framebuffer, seed, color, region) else {
+        return;
+    };
+    let inside = |framebuffer: &Framebuffer, x: i32, y: i32| {
+        in_region(framebuffer.get(x, y), target, color, region)
+    };
+    // Runs touching diagonally count as neighbours when 8-connected.
+    let reach = match connectivity {
+        Connectivity::Four => 0,
+        Connectivity::Eight => 1,
+    };
+
+    let mut stack = vec![seed];
+    while let Some([x, y]) = stack.pop() {
+        if !inside(framebuffer, x, y) {
+            continue;
+        }
+
+        let mut x0 = x;
+        while inside(framebuffer, x0 - 1, y) {
+            x0 -= 1;
+        }
+        let mut x1 = x;
+        while inside(framebuffer, x1 + 1, y) {
+            x1 += 1;
+        }
+        framebuffer.fill_span(Span { y, x0, x1 }, color);
+
+        for row in [y - 1, y + 1] {
+            let mut in_run = false;
+            for column in x0 - reach..=x1 + reach {
+                let was_in_run = in_run;
+                in_run = inside(framebuffer, column, row);
+                if in_run && !was_in_run {
+                    stack.push([column, row]);
+                }
+            }
+        }
+    }
+}
+
+/// The colour of the seed pixel, or `None` if there is nothing to fill.
+fn seed_color(
+    framebuffer: &Framebuffer,
+    seed: [i32; 2],
+    color: Color,
+    region: SeedRegion,
+) -> Option<Color> {
+    let target = framebuffer.get(seed[0], seed[1])?;
+    // Flooding with the colour the region already has would never stop.
+    (region != SeedRegion::Flood || target != color).then_some(target)
+}
+
+/// Whether a pixel is in the region and not filled yet.
+fn in_region(pixel: Option<Color>, target: Color, color: Color, region: SeedRegion) -> bool {
+    match (region, pixel) {
+        (SeedRegion::Boundary(boundary), Some(pixel)) => pixel != boundary && pixel != color,
+        (SeedRegion::Flood, Some(pixel)) => pixel == target,
+        (_, None) => false,
+    }
+}
+
+#[test]
+fn scanline_fill_test() {
+    let count = |spans: &[Span]| -> i32 { spans.iter().map(|s| s.x1 - s.x0 + 1).sum() };
+
+    let rectangle = [[0.0, 0.0], [4.0, 0.0], [4.0, 3.0], [0.0, 3.0]];
+    assert_eq!(
+        scanline_fill(&rectangle, FillRule::EvenOdd),
+        (0..3).map(|y| Span { y, x0: 0, x1: 3 }).collect::<Vec<_>>()
+    );
+
+    // Concave, in either direction, fills exactly its area.
+    let mut u = [
+        [0.0, 0.0],
+        [6.0, 0.0],
+        [6.0, 6.0],
+        [4.0, 6.0],
+        [4.0, 2.0],
+        [2.0, 2.0],
+        [2.0, 6.0],
+        [0.0, 6.0],
+    ];
+    for _ in 0..2 {
+        for rule in [FillRule::EvenOdd, FillRule::NonZero] {
+            assert_eq!(count(&scanline_fill(&u, rule)), 36 - 8);
+        }
+        u.reverse();
+    }
+
+    // The centre of a pentagram winds twice, so only non-zero fills it.
+    let star: Vec<[f32; 2]> = (0..5)
+        .map(|i| {
+            let angle = std::f32::consts::FRAC_PI_2 + i as f32 * 4.0 * std::f32::consts::PI / 5.0;
+            [20.0 * angle.cos(), 20.0 * angle.sin()]
+        })
+        .collect();
+    let covers = |spans: &[Span], [x, y]: [i32; 2]| {
+        spans.iter().any(|s| s.y == y && (s.x0..=s.x1).contains(&x))
+    };
+    let even_odd = scanline_fill(&star, FillRule::EvenOdd);
+    let non_zero = scanline_fill(&star, FillRule::NonZero);
+    assert!(!covers(&even_odd, [0, 0]) && covers(&non_zero, [0, 0]));
+    assert!(covers(&even_odd, [0, 15]) && covers(&non_zero, [0, 15]));
+
+    let polygon = FilledPolygon::new(&rectangle, FillRule::NonZero);
+    assert_eq!(
+        Drawable::vertices(&polygon, &Viewport::default()).len(),
+        polygon.spans().len() * 6 * 6
+    );
+}
+
+#[test]
+fn seed_fill_test() {
+    use crate::circle::Circle;
+    use crate::ellipse::Ellipse;
+    use crate::framebuffer::{BLACK, WHITE};
+
+    const RED: Color = [255, 0, 0, 255];
+    let filled =
+        |framebuffer: &Framebuffer| framebuffer.pixels().iter().filter(|&&p| p == RED).count();
+
+    let mut outlines = Framebuffer::new(64, 48);
+    Circle::new([20, 24], 15).rasterize(&mut outlines, WHITE);
+    Ellipse::new([48, 24], 12, 20).rasterize(&mut outlines, WHITE);
+
+    for seed in [[20, 24], [48, 24]] {
+        let mut reference = outlines.clone();
+        boundary_fill(&mut reference, seed, WHITE, RED, Connectivity::Four);
+        assert!(filled(&reference) > 0);
+        assert_eq!(reference.get(0, 0), Some(BLACK));
+
+        // Flooding the black interior and filling it a run at a time give
+        // the same result.
+        let mut flood = outlines.clone();
+        flood_fill(&mut flood, seed, RED, Connectivity::Four);
+        assert_eq!(flood, reference);
+        for region in [SeedRegion::Boundary(WHITE), SeedRegion::Flood] {
+            let mut runs = outlines.clone();
+            scanline_seed_fill(&mut runs, seed, RED, region, Connectivity::Four);
+            assert_eq!(runs, reference);
+        }
+    }
+
+    // The midpoint outlines only touch diagonally in places, so an
+    // 8-connected fill leaks out through them.
+    let mut leaked = outlines.clone();
+    boundary_fill(&mut leaked, [20, 24], WHITE, RED, Connectivity::Eight);
+    assert_eq!(leaked.get(0, 0), Some(RED));
+    let mut runs = outlines.clone();
+    scanline_seed_fill(
+        &mut runs,
+        [20, 24],
+        RED,
+        SeedRegion::Boundary(WHITE),
+        Connectivity::Eight,
+    );
+    assert_eq!(runs, leaked);
+
+    // Seeds on the outline or outside the framebuffer fill nothing.
+    let mut untouched = outlines.clone();
+    boundary_fill(&mut untouched, [5, 24], WHITE, RED, Connectivity::Four);
+    flood_fill(&mut untouched, [-1, 0], RED, Connectivity::Four);
+    assert_eq!(untouched, outlines);
+}
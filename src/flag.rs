@@ -1,5 +1,9 @@
-use crate::opengl;
-use crate::opengl::*;
+use crate::clip::sutherland_hodgman;
+use crate::drawable::{Drawable, VertexLayout};
+use crate::opengl::gl;
+use crate::shader::ShaderProgram;
+use crate::transform::Transform2D;
+use crate::viewport::Viewport;
 
 const VERTEX_SHADER_SOURCE: &[u8] = b"
 #version 100
@@ -8,90 +12,14 @@ attribute vec2 position;
 attribute vec3 color;
 varying vec3 v_color;
 
-const float PI = 3.141592654;
-
-const float ANGLE = PI/2.0;
-
-mat3 rotation_mat = mat3(
-    cos(ANGLE), sin(ANGLE),   0.0,
-    -sin(ANGLE), cos(ANGLE),   0.0,
-    0.0,      0.0,   1.0
-);
-
-mat3 original = mat3(
-    1.0, 0.0, 0.0,
-    0.0, 1.0, 0.0,
-    0.0, 0.0, 1.0
-);
-
-mat3 translation_mat = mat3(
-    1.0, 0.0, 0.0,
-    0.0, 1.0, 0.0,
-    -0.5, -0.2, 1.0
-);
-
-mat3 scaling_mat = mat3(
-    2.0, 0.0, 0.0,
-    0.0, 2.0, 0.0,
-    0.0, 0.0, 1.0
-);
-
-// Reflection in XY
-mat3 reflection_mat_1 = mat3(
-    0.0, 1.0, 0.0,
-    1.0, 0.0, 0.0,
-    0.0, 0.0, 1.0
-);
-
-// Reflection in X = -Y
-mat3 reflection_mat_2 = mat3(
-    0.0, -1.0, 0.0,
-    -1.0, 0.0, 0.0,
-    0.0, 0.0, 1.0
-);
-
-// Reflection in X
-mat3 reflection_mat_3 = mat3(
-    1.0,  0.0, 0.0,
-    0.0, -1.0, 0.0,
-    0.0,  0.0, 1.0
-);
-
-// Reflection in Y
-mat3 reflection_mat_4 = mat3(
-    -1.0, 0.0, 0.0,
-    0.0,  1.0, 0.0,
-    0.0,  0.0, 1.0
-);
-
-// Reflection about origin
-mat3 reflection_mat_5 = mat3(
-    -1.0, 0.0, 0.0,
-    0.0,  -1.0, 0.0,
-    0.0,  0.0, 1.0
-);
-
-// Y Shear
-mat3 shear_y = mat3(
-    1.0, 0.2, 0.0,
-    0.0,  1.0, 0.0,
-    0.0,  0.0, 1.0
-);
-
-// X Shear
-mat3 shear_x = mat3(
-    1.0, 0.0, 0.0,
-    0.2,  1.0, 0.0,
-    0.0,  0.0, 1.0
-);
+uniform mat3 transform;
 
 void main() {
-    gl_Position = vec4(shear_y * vec3(position, 1.0), 1.0);
+    gl_Position = vec4(transform * vec3(position, 1.0), 1.0);
     v_color = color;
 }
 \0";
 
-
 const FRAGMENT_SHADER_SOURCE: &[u8] = b"
 #version 100
 precision mediump float;
@@ -104,107 +32,41 @@ void main() {
 pub struct Flag {
     pub vertices: Vec<f32>,
     pub indices: Vec<u32>,
+    pub transform: Transform2D,
 }
 
 impl Flag {
-    pub unsafe fn drawer(renderer: &mut crate::opengl::Renderer) -> () {
-        let vertex_shader =
-            opengl::create_shader(&renderer.gl, gl::VERTEX_SHADER, VERTEX_SHADER_SOURCE);
-        let fragment_shader =
-            create_shader(&renderer.gl, gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SOURCE);
-
-        renderer.program = Some(renderer.gl.CreateProgram());
-
-        renderer
-            .gl
-            .AttachShader(renderer.program.unwrap(), vertex_shader);
-
-        renderer
-            .gl
-            .AttachShader(renderer.program.unwrap(), fragment_shader);
-
-        renderer.gl.LinkProgram(renderer.program.unwrap());
-
-        renderer.gl.UseProgram(renderer.program.unwrap());
-
-        renderer.gl.GenVertexArrays(1, &mut renderer.vao);
-        renderer.gl.BindVertexArray(renderer.vao);
-
-        renderer.gl.GenBuffers(1, &mut renderer.vbo);
-        renderer.gl.BindBuffer(gl::ARRAY_BUFFER, renderer.vbo);
-
-        let line = Self::default();
-
-        let vertex_data = line.vertices;
-        println!("{:?}", vertex_data);
-
-        let vertex_indices: Vec<u32> = (0..vertex_data.len() as u32).collect();
-
-        let mut indices: gl::types::GLuint = std::mem::zeroed();
-
-        renderer.gl.GenBuffers(1, &mut indices);
-        renderer.gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, indices);
-        renderer.gl.BufferData(
-            gl::ELEMENT_ARRAY_BUFFER,
-            (vertex_indices.len() * std::mem::size_of::<u32>()) as gl::types::GLsizeiptr,
-            vertex_indices.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        );
-
-        renderer.gl.BufferData(
-            gl::ARRAY_BUFFER,
-            (vertex_data.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
-            vertex_data.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        );
-
-        // POSITION Attribute
-        let pos_attrib = renderer.gl.GetAttribLocation(
-            renderer.program.unwrap(),
-            b"position\0".as_ptr() as *const _,
-        );
-
-        renderer.gl.VertexAttribPointer(
-            pos_attrib as gl::types::GLuint,
-            3,
-            gl::FLOAT,
-            0,
-            6 * std::mem::size_of::<f32>() as gl::types::GLsizei,
-            std::ptr::null(),
-        );
-
-        // COLOR Attribute
-        let color_attrib = renderer
-            .gl
-            .GetAttribLocation(renderer.program.unwrap(), b"color\0".as_ptr() as *const _);
-
-        renderer.gl.VertexAttribPointer(
-            color_attrib as gl::types::GLuint,
-            3,
-            gl::FLOAT,
-            0,
-            6 * std::mem::size_of::<f32>() as gl::types::GLsizei,
-            (3 * std::mem::size_of::<f32>()) as *const _,
-        );
-
-        renderer
-            .gl
-            .EnableVertexAttribArray(pos_attrib as gl::types::GLuint);
-
-        renderer
-            .gl
-            .EnableVertexAttribArray(color_attrib as gl::types::GLuint);
-
-        renderer.gl.ClearColor(0.1, 0.1, 0.1, 0.9);
-
-        renderer.gl.Clear(gl::COLOR_BUFFER_BIT);
-
-        renderer.gl.DrawElements(
-            gl::TRIANGLES,
-            vertex_indices.len() as i32,
-            gl::UNSIGNED_INT,
-            0 as *const _,
-        );
+    /// The flag with its transform applied and every triangle cut down to
+    /// the convex polygon `window`, in normalized device coordinates.
+    pub fn clipped(&self, window: &[[f32; 2]]) -> Self {
+        let vertex = |i: u32| &self.vertices[i as usize * 6..i as usize * 6 + 6];
+
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        for triangle in self.indices.chunks_exact(3) {
+            let corners: Vec<[f32; 2]> = triangle
+                .iter()
+                .map(|&i| self.transform.apply([vertex(i)[0], vertex(i)[1]]))
+                .collect();
+            let color = &vertex(triangle[0])[3..];
+
+            // Whatever is left of a triangle is convex, so a fan covers it.
+            let polygon = sutherland_hodgman(&corners, window);
+            let first = (vertices.len() / 6) as u32;
+            for &[x, y] in &polygon {
+                vertices.extend([x, y, 1.0]);
+                vertices.extend(color);
+            }
+            for i in 1..polygon.len().saturating_sub(1) as u32 {
+                indices.extend([first, first + i, first + i + 1]);
+            }
+        }
+
+        Self {
+            vertices,
+            indices,
+            transform: Transform2D::IDENTITY,
+        }
     }
 }
 
@@ -235,6 +97,53 @@ impl Default for Flag {
                 0.34421875, -0.42234375, 1.0, 0.862745, 0.078431, 0.235294,
             ],
             indices: vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+            transform: Transform2D::shear_y(0.2),
         }
     }
 }
+
+impl Drawable for Flag {
+    fn vertices(&self, _viewport: &Viewport) -> Vec<f32> {
+        self.vertices.clone()
+    }
+
+    fn layout(&self) -> VertexLayout {
+        VertexLayout::position_color(3)
+    }
+
+    fn primitive(&self) -> gl::types::GLenum {
+        gl::TRIANGLES
+    }
+
+    fn indices(&self, _vertex_count: usize) -> Vec<u32> {
+        self.indices.clone()
+    }
+
+    fn vertex_shader(&self) -> &'static [u8] {
+        VERTEX_SHADER_SOURCE
+    }
+
+    fn fragment_shader(&self) -> &'static [u8] {
+        FRAGMENT_SHADER_SOURCE
+    }
+
+    unsafe fn set_uniforms(&self, gl: &gl::Gl, program: &ShaderProgram, _viewport: &Viewport) {
+        let location = program.uniform_location(gl, c"transform");
+        self.transform.upload(gl, location);
+    }
+}
+
+#[test]
+fn clipped_flag_test() {
+    let flag = Flag::default();
+    let border = [[-0.25, -0.5], [0.3, -0.5], [0.3, 0.5], [-0.25, 0.5]];
+    let clipped = flag.clipped(&border);
+
+    assert_eq!(clipped.indices.len() % 3, 0);
+    for vertex in clipped.vertices.chunks_exact(6) {
+        assert!((-0.25..=0.3).contains(&vertex[0]), "{vertex:?}");
+        assert!((-0.5..=0.5).contains(&vertex[1]), "{vertex:?}");
+    }
+    // Every triangle keeps its colour.
+    assert_eq!(&clipped.vertices[3..6], &flag.vertices[3..6]);
+}
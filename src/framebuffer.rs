@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+
+pub type Color = [u8; 4];
+
+pub const BLACK: Color = [0, 0, 0, 255];
+pub const WHITE: Color = [255, 255, 255, 255];
+
+/// A horizontal run of pixels on row `y`, from `x0` to `x1` inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Span {
+    pub y: i32,
+    pub x0: i32,
+    pub x1: i32,
+}
+
+/// Anything a rasterizer can plot integer pixels into.
+pub trait Canvas {
+    fn plot(&mut self, x: i32, y: i32, color: Color);
+
+    /// Plots a pixel `coverage` (0 to 1) of which lies under the shape, used
+    /// by the anti-aliasing rasterizers. Canvases that cannot blend plot the
+    /// pixels that are at least half covered.
+    fn blend(&mut self, x: i32, y: i32, color: Color, coverage: f32) {
+        if coverage >= 0.5 {
+            self.plot(x, y, color);
+        }
+    }
+
+    /// Plots every pixel of `span`, used by the filling rasterizers.
+    fn fill_span(&mut self, span: Span, color: Color) {
+        for x in span.x0..=span.x1 {
+            self.plot(x, span.y, color);
+        }
+    }
+}
+
+/// Collects plotted pixels as a point list, ignoring the colour. This is what
+/// the shapes keep around to build their OpenGL vertex data.
+impl Canvas for Vec<[f32; 2]> {
+    fn plot(&mut self, x: i32, y: i32, _color: Color) {
+        self.push([x as f32, y as f32]);
+    }
+}
+
+/// Collects plotted pixels as a point list along with how much of each pixel
+/// is covered, 1 for everything but the anti-aliasing rasterizers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Points {
+    pub points: Vec<[f32; 2]>,
+    pub coverage: Vec<f32>,
+}
+
+impl Canvas for Points {
+    fn plot(&mut self, x: i32, y: i32, color: Color) {
+        self.blend(x, y, color, 1.0);
+    }
+
+    fn blend(&mut self, x: i32, y: i32, _color: Color, coverage: f32) {
+        self.points.push([x as f32, y as f32]);
+        self.coverage.push(coverage);
+    }
+}
+
+/// Merges pixels a rasterizer reaches more than once, such as the mirrored
+/// points of a symmetric curve, keeping the most coverage any one plot gave
+/// each.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Coverage {
+    pub pixels: BTreeMap<[i32; 2], f32>,
+}
+
+impl Coverage {
+    /// Draws every pixel into `canvas` once, blending the partly covered ones.
+    pub fn rasterize(&self, canvas: &mut impl Canvas, color: Color) {
+        for (&[x, y], &coverage) in &self.pixels {
+            if coverage < 1.0 {
+                canvas.blend(x, y, color, coverage);
+            } else {
+                canvas.plot(x, y, color);
+            }
+        }
+    }
+}
+
+impl Canvas for Coverage {
+    fn plot(&mut self, x: i32, y: i32, color: Color) {
+        self.blend(x, y, color, 1.0);
+    }
+
+    fn blend(&mut self, x: i32, y: i32, _color: Color, coverage: f32) {
+        if coverage > 0.0 {
+            let merged = self.pixels.entry([x, y]).or_insert(0.0);
+            *merged = merged.max(coverage.min(1.0));
+        }
+    }
+}
+
+/// CPU side RGBA image with the origin at the bottom left, matching the
+/// coordinates the rasterizers produce.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![BLACK; width as usize * height as usize],
+        }
+    }
+
+    /// Wraps pixels stored bottom row first.
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.pixels.fill(color);
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<Color> {
+        self.index(x, y).map(|i| self.pixels[i])
+    }
+
+    /// Rows from top to bottom as tightly packed RGBA bytes, the layout image
+    /// formats expect.
+    pub fn to_rgba_bytes(&self) -> Vec<u8> {
+        self.pixels
+            .chunks(self.width.max(1) as usize)
+            .rev()
+            .flat_map(|row| row.iter().flatten().copied())
+            .collect()
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        self.contains(x, y)
+            .then(|| y as usize * self.width as usize + x as usize)
+    }
+}
+
+impl Canvas for Framebuffer {
+    /// Pixels outside the framebuffer are silently dropped, the same way the
+    /// GPU discards points outside normalized device coordinates.
+    fn plot(&mut self, x: i32, y: i32, color: Color) {
+        if let Some(i) = self.index(x, y) {
+            self.pixels[i] = color;
+        }
+    }
+
+    fn fill_span(&mut self, span: Span, color: Color) {
+        if span.y < 0 || span.y >= self.height as i32 {
+            return;
+        }
+        let x0 = span.x0.max(0);
+        let x1 = span.x1.min(self.width as i32 - 1);
+        if x0 <= x1 {
+            let row = span.y as usize * self.width as usize;
+            self.pixels[row + x0 as usize..=row + x1 as usize].fill(color);
+        }
+    }
+
+    /// Mixes `color` over what is already there in proportion to `coverage`.
+    fn blend(&mut self, x: i32, y: i32, color: Color, coverage: f32) {
+        if let Some(i) = self.index(x, y) {
+            let coverage = coverage.clamp(0.0, 1.0);
+            let under = self.pixels[i];
+            self.pixels[i] = std::array::from_fn(|c| {
+                (under[c] as f32 + (color[c] as f32 - under[c] as f32) * coverage).round() as u8
+            });
+        }
+    }
+}
+
+#[test]
+fn framebuffer_test() {
+    let mut framebuffer = Framebuffer::new(4, 3);
+    framebuffer.plot(0, 0, WHITE);
+    framebuffer.plot(3, 2, WHITE);
+    framebuffer.plot(4, 0, WHITE);
+    framebuffer.plot(-1, 1, WHITE);
+
+    assert_eq!(framebuffer.get(0, 0), Some(WHITE));
+    assert_eq!(framebuffer.get(3, 2), Some(WHITE));
+    assert_eq!(framebuffer.get(4, 0), None);
+    assert_eq!(
+        framebuffer.pixels().iter().filter(|&&p| p == WHITE).count(),
+        2
+    );
+
+    // Top row first.
+    let bytes = framebuffer.to_rgba_bytes();
+    assert_eq!(&bytes[12..16], &WHITE);
+    assert_eq!(&bytes[32..36], &WHITE);
+
+    framebuffer.fill_span(
+        Span {
+            y: 1,
+            x0: -2,
+            x1: 1,
+        },
+        WHITE,
+    );
+    assert_eq!(framebuffer.get(0, 1), Some(WHITE));
+    assert_eq!(framebuffer.get(1, 1), Some(WHITE));
+    assert_eq!(framebuffer.get(2, 1), Some(BLACK));
+
+    framebuffer.blend(1, 0, WHITE, 0.25);
+    assert_eq!(framebuffer.get(1, 0), Some([64, 64, 64, 255]));
+
+    // Empty framebuffers have no rows to read back.
+    assert!(Framebuffer::new(0, 3).to_rgba_bytes().is_empty());
+}
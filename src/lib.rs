@@ -0,0 +1,22 @@
+pub mod arc;
+pub mod camera;
+pub mod circle;
+pub mod cli;
+pub mod clip;
+pub mod cube;
+pub mod dash;
+pub mod drawable;
+pub mod ellipse;
+#[cfg(feature = "png")]
+pub mod export;
+pub mod fill;
+pub mod flag;
+pub mod framebuffer;
+pub mod line;
+pub mod opengl;
+pub mod polyline;
+pub mod shader;
+pub mod stroke;
+pub mod transform;
+pub mod transform3d;
+pub mod viewport;
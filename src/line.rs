@@ -1,13 +1,44 @@
+use crate::clip::{cyrus_beck, liang_barsky};
+use crate::dash::{Dash, Dashed};
+use crate::drawable::{Drawable, VertexLayout};
+use crate::framebuffer::{Canvas, Color, Points, WHITE};
+use crate::opengl::gl;
+use crate::stroke::{Stroke, StrokedPath};
+use crate::transform::transform_points;
+use crate::viewport::{Rect, Viewport};
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum LineAlgorithm {
     DDA,
     Bresenham,
     Midpoint,
+    /// Xiaolin Wu's anti-aliased line, plotting the two pixels straddling
+    /// the line at every step with their coverage.
+    Wu,
+}
+
+impl std::str::FromStr for LineAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "dda" => Ok(LineAlgorithm::DDA),
+            "bresenham" => Ok(LineAlgorithm::Bresenham),
+            "midpoint" => Ok(LineAlgorithm::Midpoint),
+            "wu" => Ok(LineAlgorithm::Wu),
+            _ => Err(format!("unknown line algorithm `{}`", s)),
+        }
+    }
 }
 
 pub struct Line {
     points: Vec<[f32; 2]>,
+    coverage: Vec<f32>,
     x1: [f32; 2],
     x2: [f32; 2],
+    algo: LineAlgorithm,
+    dash: Option<Dash>,
 }
 
 impl Line {
@@ -15,157 +46,395 @@ impl Line {
         let mut line = Self {
             x1,
             x2,
+            algo,
+            dash: None,
             points: vec![],
+            coverage: vec![],
         };
+        line.collect_points();
+        line
+    }
 
-        match algo {
-            LineAlgorithm::DDA => line.generate_dda_line(),
-            LineAlgorithm::Bresenham => line.generate_bresenham_line(),
-            LineAlgorithm::Midpoint => line.generate_midpoint_line(),
-        }
+    /// The part of the line inside `window`, or `None` if it misses the
+    /// window entirely. Clipped with Liang–Barsky; see `crate::clip` for the
+    /// intermediate values.
+    pub fn clipped(x1: [f32; 2], x2: [f32; 2], algo: LineAlgorithm, window: &Rect) -> Option<Self> {
+        let [x1, x2] = liang_barsky(x1, x2, window).segment?;
+        Some(Self::new(x1, x2, algo))
+    }
 
-        return line;
+    /// The part of the line inside the convex polygon `window`, clipped with
+    /// Cyrus–Beck.
+    pub fn clipped_convex(
+        x1: [f32; 2],
+        x2: [f32; 2],
+        algo: LineAlgorithm,
+        window: &[[f32; 2]],
+    ) -> Option<Self> {
+        let [x1, x2] = cyrus_beck(x1, x2, window).segment?;
+        Some(Self::new(x1, x2, algo))
     }
 
-    fn generate_dda_line(&mut self) {
-        let dx = self.x2[0] - self.x1[0];
-        let dy = self.x2[1] - self.x1[1];
+    /// Draws only the pixels on the dashes of `dash`, whatever the algorithm.
+    pub fn with_dash(mut self, dash: Dash) -> Self {
+        self.dash = Some(dash);
+        self.collect_points();
+        self
+    }
 
-        let steps: u32;
-        if dx.abs() > dy.abs() {
-            steps = dx.abs() as u32;
-        } else {
-            steps = dy.abs() as u32;
-        }
+    pub fn dash(&self) -> Option<&Dash> {
+        self.dash.as_ref()
+    }
 
-        let x_increment = dx / steps as f32;
-        let y_increment = dy / steps as f32;
+    /// The dash pattern carried on from where this line leaves off, for the
+    /// next segment of a polyline starting at this line's end.
+    pub fn next_dash(&self) -> Option<Dash> {
+        let ([x0, y0], [x1, y1]) = self.pixel_endpoints();
+        let steps = x0.abs_diff(x1).max(y0.abs_diff(y1));
+        let dash = self.dash.clone()?;
+        let phase = dash.phase() + steps % dash.period();
+        Some(dash.with_phase(phase))
+    }
 
-        let mut x = self.x1[0];
-        let mut y = self.x1[1];
+    fn collect_points(&mut self) {
+        let mut points = Points::default();
+        self.rasterize(&mut points, WHITE);
+        self.points = points.points;
+        self.coverage = points.coverage;
+    }
 
-        self.points.push([x.round(), y.round()]);
-        for _ in 0..steps {
-            x += x_increment;
-            y += y_increment;
-            self.points.push([x.round(), y.round()]);
+    /// Plots the line straight into `canvas` with the algorithm it was
+    /// created with, skipping the gaps of its dash pattern if it has one.
+    pub fn rasterize(&self, canvas: &mut impl Canvas, color: Color) {
+        match &self.dash {
+            Some(dash) => {
+                let ([x0, y0], [x1, y1]) = self.pixel_endpoints();
+                let steep = y0.abs_diff(y1) > x0.abs_diff(x1);
+                self.rasterize_solid(&mut Dashed::new(canvas, dash, [x0, y0], steep), color)
+            }
+            None => self.rasterize_solid(canvas, color),
         }
     }
 
-    fn generate_bresenham_line(&mut self) {
-        let x0 = self.x1[0];
-        let x1 = self.x2[0];
-        let y0 = self.x1[1];
-        let y1 = self.x2[1];
-
-        let dx = (x1 - x0).abs();
-        let dy = (y1 - y0).abs();
-
-        let sx = if x0 < x1 { 1 } else { -1 };
-        let sy = if y0 < y1 { 1 } else { -1 };
+    fn rasterize_solid(&self, canvas: &mut impl Canvas, color: Color) {
+        match self.algo {
+            LineAlgorithm::DDA => self.generate_dda_line(canvas, color),
+            LineAlgorithm::Bresenham => self.generate_bresenham_line(canvas, color),
+            LineAlgorithm::Midpoint => self.generate_midpoint_line(canvas, color),
+            LineAlgorithm::Wu => self.generate_wu_line(canvas, color),
+        }
+    }
 
-        let mut x = x0;
-        let mut y = y0;
+    /// Endpoints snapped to the pixel centres nearest to them. The aliased
+    /// algorithms work on these in integer arithmetic only.
+    fn pixel_endpoints(&self) -> ([i32; 2], [i32; 2]) {
+        let snap = |[x, y]: [f32; 2]| [x.round() as i32, y.round() as i32];
+        (snap(self.x1), snap(self.x2))
+    }
 
-        if dx > dy {
-            let mut err = dx / 2.0;
+    /// Steps once per pixel along the major axis and moves along the minor
+    /// axis by `d_minor / steps` each time. The running numerator is kept as
+    /// an integer and divided, rounded, on every step instead of accumulating
+    /// a float increment.
+    fn generate_dda_line(&self, canvas: &mut impl Canvas, color: Color) {
+        let ([x0, y0], [x1, y1]) = self.pixel_endpoints();
+        let dx = x1 as i64 - x0 as i64;
+        let dy = y1 as i64 - y0 as i64;
+        let steps = dx.abs().max(dy.abs());
 
-            while x != x1 {
-                self.points.push([x, y]);
+        if steps == 0 {
+            canvas.plot(x0, y0, color);
+            return;
+        }
 
-                err -= dy;
-                if err < 0.0 {
-                    y += sy as f32;
-                    err += dx;
-                }
+        for i in 0..=steps {
+            let x = x0 as i64 + div_round(i * dx, steps);
+            let y = y0 as i64 + div_round(i * dy, steps);
+            canvas.plot(x as i32, y as i32, color);
+        }
+    }
 
-                x += sx as f32;
-            }
-        } else {
-            let mut err = dy / 2.0;
+    /// Bresenham's error term, scaled by `2 * d_major` so it stays an
+    /// integer. Steep lines swap the roles of x and y.
+    fn generate_bresenham_line(&self, canvas: &mut impl Canvas, color: Color) {
+        let ([x0, y0], [x1, y1]) = self.pixel_endpoints();
+        let dx = (x1 as i64 - x0 as i64).abs();
+        let dy = (y1 as i64 - y0 as i64).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
 
-            while y != y1 {
-                self.points.push([x, y]);
+        let steep = dy > dx;
+        let (major, minor) = if steep { (dy, dx) } else { (dx, dy) };
 
-                err -= dx;
-                if err < 0.0 {
-                    x += sx as f32;
-                    err += dy;
+        let (mut x, mut y) = (x0, y0);
+        // Distance from the line to the pixel centre along the minor axis,
+        // times 2 * major, minus major.
+        let mut err = 2 * minor - major;
+        for _ in 0..=major {
+            canvas.plot(x, y, color);
+            if err > 0 {
+                if steep {
+                    x += sx;
+                } else {
+                    y += sy;
                 }
-
-                y += sy as f32;
+                err -= 2 * major;
+            }
+            err += 2 * minor;
+            if steep {
+                y += sy;
+            } else {
+                x += sx;
             }
         }
-
-        self.points.push([x, y]);
     }
 
-    fn generate_midpoint_line(&mut self) {
-        let x0 = self.x1[0];
-        let x1 = self.x2[0];
-        let y0 = self.x1[1];
-        let y1 = self.x2[1];
-
-        let mut x = x0;
-        let mut y = y0;
-        let dx = (x1 - x0).abs();
-        let dy = (y1 - y0).abs();
+    /// Evaluates the implicit line equation at the midpoint between the two
+    /// candidate pixels of the next step and picks the one on the same side
+    /// as the line. The equation is taken with `|dx|` and `|dy|` so one pair
+    /// of increments serves all eight octants.
+    fn generate_midpoint_line(&self, canvas: &mut impl Canvas, color: Color) {
+        let ([x0, y0], [x1, y1]) = self.pixel_endpoints();
+        let dx = (x1 as i64 - x0 as i64).abs();
+        let dy = (y1 as i64 - y0 as i64).abs();
         let sx = if x0 < x1 { 1 } else { -1 };
         let sy = if y0 < y1 { 1 } else { -1 };
 
-        if dy >= dx {
-            let mut err = if dx > dy { dy } else { -dx } / 2.0;
+        let (mut x, mut y) = (x0, y0);
+        canvas.plot(x, y, color);
 
-            loop {
-                self.points.push([x, y]);
-                if x == x1 && y == y1 {
-                    break;
-                }
-                let e2 = err;
-                if e2 > -dx {
-                    err -= dy;
-                    x += sx as f32;
-                }
-                if e2 < dy {
-                    err += dx;
-                    y += sy as f32;
+        if dx >= dy {
+            // F(x, y) = dy * x - dx * y, doubled, at (x + 1, y + 1/2).
+            let mut d = 2 * dy - dx;
+            for _ in 0..dx {
+                if d > 0 {
+                    y += sy;
+                    d += 2 * (dy - dx);
+                } else {
+                    d += 2 * dy;
                 }
+                x += sx;
+                canvas.plot(x, y, color);
             }
         } else {
-            let mut err = if dx > dy { dx } else { -dy } / 2.0;
-
-            loop {
-                self.points.push([x, y]);
-                if x == x1 && y == y1 {
-                    break;
-                }
-                let e2 = err;
-                if e2 > -dx {
-                    err -= dy;
-                    x += sx as f32;
-                }
-                if e2 < dy {
-                    err += dx;
-                    y += sy as f32;
+            // The same with x and y swapped, at (x + 1/2, y + 1).
+            let mut d = 2 * dx - dy;
+            for _ in 0..dy {
+                if d > 0 {
+                    x += sx;
+                    d += 2 * (dx - dy);
+                } else {
+                    d += 2 * dx;
                 }
+                y += sy;
+                canvas.plot(x, y, color);
+            }
+        }
+    }
+
+    fn generate_wu_line(&self, canvas: &mut impl Canvas, color: Color) {
+        let [mut x0, mut y0] = self.x1;
+        let [mut x1, mut y1] = self.x2;
+
+        // Walk along the major axis, left to right.
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            std::mem::swap(&mut x0, &mut y0);
+            std::mem::swap(&mut x1, &mut y1);
+        }
+        if x0 > x1 {
+            std::mem::swap(&mut x0, &mut x1);
+            std::mem::swap(&mut y0, &mut y1);
+        }
+
+        let mut plot = |x: f32, y: f32, coverage: f32| {
+            if coverage > 0.0 {
+                let (x, y) = if steep { (y, x) } else { (x, y) };
+                canvas.blend(x as i32, y as i32, color, coverage);
             }
+        };
+
+        let dx = x1 - x0;
+        let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+        // The endpoints only cover the part of their pixel the line reaches
+        // into.
+        let x_start = x0.round();
+        let y_start = y0 + gradient * (x_start - x0);
+        let gap = 1.0 - fpart(x0 + 0.5);
+        plot(x_start, y_start.floor(), (1.0 - fpart(y_start)) * gap);
+        plot(x_start, y_start.floor() + 1.0, fpart(y_start) * gap);
+
+        let x_end = x1.round();
+        let y_end = y1 + gradient * (x_end - x1);
+        if x_end > x_start {
+            let gap = fpart(x1 + 0.5);
+            plot(x_end, y_end.floor(), (1.0 - fpart(y_end)) * gap);
+            plot(x_end, y_end.floor() + 1.0, fpart(y_end) * gap);
+        }
+
+        let mut y = y_start + gradient;
+        let mut x = x_start + 1.0;
+        while x < x_end {
+            plot(x, y.floor(), 1.0 - fpart(y));
+            plot(x, y.floor() + 1.0, fpart(y));
+            y += gradient;
+            x += 1.0;
         }
     }
 
-    pub fn get_normalized_coordinate(&self) -> Vec<f32> {
-        return self
-            .points
+    pub fn points(&self) -> &[[f32; 2]] {
+        &self.points
+    }
+
+    /// How much of each of `points` the line covers, all 1 unless the line
+    /// was drawn with `LineAlgorithm::Wu`.
+    pub fn coverage(&self) -> &[f32] {
+        &self.coverage
+    }
+
+    /// Vertices laid out as `VertexLayout::position_color_alpha(3)`, with the
+    /// coverage of each point in the alpha channel.
+    pub fn get_normalized_coordinate(&self, viewport: &Viewport) -> Vec<f32> {
+        let window_to_ndc = viewport.window_to_ndc();
+        self.points
             .iter()
-            .flat_map(|item| {
+            .zip(&self.coverage)
+            .flat_map(|(&item, &coverage)| {
+                let [x, y] = window_to_ndc.apply(item);
                 [
-                    (item[0] / 800.0) - 1.0,
-                    (item[1] / 600.0) - 1.0,
-                    0.0, // Z
+                    x, y, 0.0, // Z
                     1.0, // R
                     1.0, // G
                     1.0, // B
+                    coverage,
                 ]
             })
-            .collect();
+            .collect()
+    }
+
+    /// The line between the original endpoints drawn `stroke.width` wide.
+    pub fn stroke(&self, stroke: &Stroke) -> StrokedPath {
+        StrokedPath::new(&[self.x1, self.x2], false, stroke)
     }
+
+    /// Applies a row-major 2D homogeneous matrix, or a `Transform2D`, to the
+    /// rasterized points.
+    /// The endpoints used by `rasterize` are left untouched.
+    pub fn transform(&mut self, matrix: impl Into<[[f32; 3]; 3]>) {
+        transform_points(&mut self.points, matrix.into());
+    }
+}
+
+/// `numerator / denominator` rounded to the nearest integer, ties towards
+/// zero. `denominator` must be positive.
+///
+/// Ties go the same way as in the Bresenham and midpoint decision variables,
+/// which only step along the minor axis once the line is strictly past the
+/// midpoint, so all three algorithms plot the same pixels.
+fn div_round(numerator: i64, denominator: i64) -> i64 {
+    let rounded = (2 * numerator.abs() + denominator - 1) / (2 * denominator);
+    rounded * numerator.signum()
+}
+
+/// Fractional part measured from the floor, so it stays within [0, 1) for
+/// negative coordinates too.
+fn fpart(value: f32) -> f32 {
+    value - value.floor()
+}
+
+impl Drawable for Line {
+    fn vertices(&self, viewport: &Viewport) -> Vec<f32> {
+        self.get_normalized_coordinate(viewport)
+    }
+
+    fn layout(&self) -> VertexLayout {
+        VertexLayout::position_color_alpha(3)
+    }
+
+    fn primitive(&self) -> gl::types::GLenum {
+        gl::POINTS
+    }
+}
+
+#[test]
+fn clipped_line_test() {
+    let window = Rect::new(0.0, 0.0, 799.0, 599.0);
+    let line = Line::clipped(
+        [-100.0, 300.0],
+        [1000.0, 300.0],
+        LineAlgorithm::Bresenham,
+        &window,
+    )
+    .unwrap();
+    assert_eq!(line.points().first(), Some(&[0.0, 300.0]));
+    assert_eq!(line.points().last(), Some(&[799.0, 300.0]));
+    assert!(Line::clipped([900.0, 0.0], [1000.0, 600.0], LineAlgorithm::DDA, &window).is_none());
+
+    let triangle = [[0.0, 0.0], [40.0, 0.0], [0.0, 40.0]];
+    let line =
+        Line::clipped_convex([-10.0, 10.0], [50.0, 10.0], LineAlgorithm::Wu, &triangle).unwrap();
+    assert!(line.points().iter().all(|p| (0.0..=30.0).contains(&p[0])));
+}
+
+#[test]
+fn dashed_line_test() {
+    // Every algorithm drops the same pixels, the anti-aliased one whole
+    // columns at a time.
+    for algo in [
+        LineAlgorithm::DDA,
+        LineAlgorithm::Bresenham,
+        LineAlgorithm::Midpoint,
+        LineAlgorithm::Wu,
+    ] {
+        let line = Line::new([20.0, 3.0], [0.0, 10.0], algo).with_dash(Dash::new(&[3, 2]));
+        assert!(!line.points().is_empty());
+        for point in line.points() {
+            assert!((20 - point[0] as u32) % 5 < 3, "{algo:?} {point:?}");
+        }
+    }
+
+    // Steep lines count along y.
+    let line =
+        Line::new([0.0, 0.0], [3.0, 9.0], LineAlgorithm::Bresenham).with_dash(Dash::new(&[1, 1]));
+    assert!(line
+        .points()
+        .iter()
+        .all(|p| (p[1] as u32).is_multiple_of(2)));
+
+    // The next segment picks up the pattern where this one stopped.
+    let line = Line::new([0.0, 0.0], [7.0, 2.0], LineAlgorithm::DDA)
+        .with_dash(Dash::new(&[3, 2]).with_phase(1));
+    assert_eq!(line.next_dash(), Some(Dash::new(&[3, 2]).with_phase(3)));
+}
+
+#[test]
+fn wu_line_test() {
+    let line = Line::new([0.0, 0.0], [8.0, 3.0], LineAlgorithm::Wu);
+    assert_eq!(line.points().len(), line.coverage().len());
+
+    // Every column is shared between the two pixels straddling the line, the
+    // endpoints only get the half of their pixel the line reaches into.
+    for x in 0..=8 {
+        let column: f32 = line
+            .points()
+            .iter()
+            .zip(line.coverage())
+            .filter(|(point, _)| point[0] == x as f32)
+            .map(|(_, coverage)| coverage)
+            .sum();
+        let expected = if x == 0 || x == 8 { 0.5 } else { 1.0 };
+        assert!((column - expected).abs() < 1e-5, "column {x}: {column}");
+    }
+
+    // Steep lines walk along y instead.
+    let line = Line::new([2.0, 0.0], [-1.0, 6.0], LineAlgorithm::Wu);
+    for y in 1..6 {
+        let row = line.points().iter().filter(|p| p[1] == y as f32).count();
+        assert!(row <= 2, "row {y}");
+    }
+
+    let vertices = line.get_normalized_coordinate(&Viewport::default());
+    assert_eq!(vertices.len(), line.points().len() * 7);
+    assert_eq!(vertices[6], line.coverage()[0]);
 }
@@ -1,144 +1,193 @@
-mod circle;
-mod ellipse;
-mod flag;
-mod line;
-mod opengl;
-mod cube;
-
-use circle::Circle;
-use ellipse::Ellipse;
-use line::{Line, LineAlgorithm};
-use opengl::*;
-
-unsafe fn drawer(renderer: &mut opengl::Renderer) -> () {
-    let vertex_shader =
-        opengl::create_shader(&renderer.gl, gl::VERTEX_SHADER, VERTEX_SHADER_SOURCE);
-    let fragment_shader = create_shader(&renderer.gl, gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SOURCE);
-
-    renderer.program = Some(renderer.gl.CreateProgram());
-
-    renderer
-        .gl
-        .AttachShader(renderer.program.unwrap(), vertex_shader);
-
-    renderer
-        .gl
-        .AttachShader(renderer.program.unwrap(), fragment_shader);
-
-    renderer.gl.LinkProgram(renderer.program.unwrap());
-
-    renderer.gl.UseProgram(renderer.program.unwrap());
-
-    renderer.gl.GenVertexArrays(1, &mut renderer.vao);
-    renderer.gl.BindVertexArray(renderer.vao);
-
-    renderer.gl.GenBuffers(1, &mut renderer.vbo);
-    renderer.gl.BindBuffer(gl::ARRAY_BUFFER, renderer.vbo);
-
-    let tm = [
-        [1.0, 0.0, 0.0],
-        [0.0, 1.0, 0.0],
-        [0.0, 0.0, 1.0],
-    ];
-
-    let mut line = circle::Circle::new([500, 500], 150);
-    line.transform(tm);
-
-    let vertex_data = line.get_normalized_coordinate();
-
-    let vertex_indices: Vec<u32> = (0..vertex_data.len() as u32).collect();
-
-    let mut indices: gl::types::GLuint = std::mem::zeroed();
-
-    renderer.gl.GenBuffers(1, &mut indices);
-    renderer.gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, indices);
-    renderer.gl.BufferData(
-        gl::ELEMENT_ARRAY_BUFFER,
-        (vertex_indices.len() * std::mem::size_of::<u32>()) as gl::types::GLsizeiptr,
-        vertex_indices.as_ptr() as *const _,
-        gl::STATIC_DRAW,
-    );
-
-    renderer.gl.BufferData(
-        gl::ARRAY_BUFFER,
-        (vertex_data.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
-        vertex_data.as_ptr() as *const _,
-        gl::STATIC_DRAW,
-    );
-
-    // POSITION Attribute
-    let pos_attrib = renderer.gl.GetAttribLocation(
-        renderer.program.unwrap(),
-        b"position\0".as_ptr() as *const _,
-    );
-
-    renderer.gl.VertexAttribPointer(
-        pos_attrib as gl::types::GLuint,
-        3,
-        gl::FLOAT,
-        0,
-        6 * std::mem::size_of::<f32>() as gl::types::GLsizei,
-        std::ptr::null(),
-    );
-
-    // COLOR Attribute
-    let color_attrib = renderer
-        .gl
-        .GetAttribLocation(renderer.program.unwrap(), b"color\0".as_ptr() as *const _);
-
-    renderer.gl.VertexAttribPointer(
-        color_attrib as gl::types::GLuint,
-        3,
-        gl::FLOAT,
-        0,
-        6 * std::mem::size_of::<f32>() as gl::types::GLsizei,
-        (3 * std::mem::size_of::<f32>()) as *const _,
-    );
-
-    renderer
-        .gl
-        .EnableVertexAttribArray(pos_attrib as gl::types::GLuint);
-
-    renderer
-        .gl
-        .EnableVertexAttribArray(color_attrib as gl::types::GLuint);
+use std::process::ExitCode;
+
+use biopix::circle::Circle;
+use biopix::cli::{self, Command, Demo, Output};
+use biopix::cube::Cube;
+use biopix::dash::Dash;
+use biopix::ellipse::Ellipse;
+use biopix::flag::Flag;
+#[cfg(feature = "png")]
+use biopix::framebuffer::{Framebuffer, WHITE};
+use biopix::line::{Line, LineAlgorithm};
+use biopix::opengl::{self, Renderer};
+use biopix::shader::ShaderError;
+
+pub fn main() -> ExitCode {
+    let (demo, output) = match cli::parse(std::env::args().skip(1)) {
+        Ok(Command::Run { demo, output }) => (demo, output),
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match output {
+        Output::Window => opengl::init(move |renderer| setup(renderer, demo)),
+        Output::Image { path, size } => return save(demo, path, size),
+    }
 
-    renderer.gl.ClearColor(0.1, 0.1, 0.1, 0.9);
+    ExitCode::SUCCESS
+}
 
-    renderer.gl.Clear(gl::COLOR_BUFFER_BIT);
+fn setup(renderer: &mut Renderer, demo: Demo) -> Result<(), ShaderError> {
+    match demo {
+        Demo::Line {
+            from,
+            to,
+            algorithm,
+            stroke,
+            dash,
+        } => {
+            let line = line(from, to, algorithm, dash);
+            match stroke {
+                Some(stroke) => renderer.add_shape(line.stroke(&stroke))?,
+                None => renderer.add_shape(line)?,
+            }
+        }
+        Demo::Circle {
+            centre,
+            radius,
+            filled,
+            antialiased,
+            arc,
+            closure,
+        } => {
+            let circle = circle(centre, radius, filled, antialiased);
+            match arc {
+                Some([start, end]) => {
+                    renderer.add_shape(circle.arc(start, end).with_closure(closure))?
+                }
+                None => renderer.add_shape(circle)?,
+            }
+        }
+        Demo::Ellipse {
+            centre,
+            a,
+            b,
+            rotation,
+            filled,
+            antialiased,
+            arc,
+            closure,
+        } => {
+            let ellipse = ellipse(centre, a, b, filled, antialiased).with_rotation(rotation);
+            match arc {
+                Some([start, end]) => {
+                    renderer.add_shape(ellipse.arc(start, end).with_closure(closure))?
+                }
+                None => renderer.add_shape(ellipse)?,
+            }
+        }
+        Demo::Flag => renderer.add_shape(Flag::default())?,
+        Demo::Cube => renderer.add_shape(Cube::new(0.5))?,
+    };
+    Ok(())
+}
 
-    renderer.gl.DrawElements(
-        gl::POINTS,
-        vertex_indices.len() as i32,
-        gl::UNSIGNED_INT,
-        0 as *const _,
-    );
+fn line(from: [f32; 2], to: [f32; 2], algorithm: LineAlgorithm, dash: Option<Dash>) -> Line {
+    let line = Line::new(from, to, algorithm);
+    match dash {
+        Some(dash) => line.with_dash(dash),
+        None => line,
+    }
 }
 
-const VERTEX_SHADER_SOURCE: &[u8] = b"
-#version 100
-precision mediump float;
-attribute vec3 position;
-attribute vec3 color;
-varying vec3 v_color;
-void main() {
-    gl_Position = vec4(position, 1.0);
-    v_color = color;
+fn circle(centre: [i32; 2], radius: i32, filled: bool, antialiased: bool) -> Circle {
+    if filled {
+        Circle::filled(centre, radius)
+    } else if antialiased {
+        Circle::antialiased(centre, radius)
+    } else {
+        Circle::new(centre, radius)
+    }
 }
-\0";
 
-const FRAGMENT_SHADER_SOURCE: &[u8] = b"
-#version 100
-precision mediump float;
-varying vec3 v_color;
-void main() {
-    gl_FragColor = vec4(v_color, 1.0);
+fn ellipse(centre: [i32; 2], a: i32, b: i32, filled: bool, antialiased: bool) -> Ellipse {
+    if filled {
+        Ellipse::filled(centre, a, b)
+    } else if antialiased {
+        Ellipse::antialiased(centre, a, b)
+    } else {
+        Ellipse::new(centre, a, b)
+    }
 }
-\0";
 
-pub fn main() {
-    // let circle = Circle::new([350, 400], 150);
-    unsafe {
-        opengl::init(Some(cube::Cube::drawer));
+/// The 2D rasterizers render straight into a framebuffer without a window,
+/// the OpenGL demos are captured from their first frame.
+#[cfg(feature = "png")]
+fn save(demo: Demo, path: std::path::PathBuf, size: [u32; 2]) -> ExitCode {
+    let mut framebuffer = Framebuffer::new(size[0], size[1]);
+    match demo {
+        Demo::Line {
+            from,
+            to,
+            algorithm,
+            stroke,
+            dash,
+        } => {
+            let line = line(from, to, algorithm, dash);
+            match stroke {
+                Some(stroke) => line.stroke(&stroke).rasterize(&mut framebuffer, WHITE),
+                None => line.rasterize(&mut framebuffer, WHITE),
+            }
+        }
+        Demo::Circle {
+            centre,
+            radius,
+            filled,
+            antialiased,
+            arc,
+            closure,
+        } => {
+            let circle = circle(centre, radius, filled, antialiased);
+            match arc {
+                Some([start, end]) => circle
+                    .arc(start, end)
+                    .with_closure(closure)
+                    .rasterize(&mut framebuffer, WHITE),
+                None => circle.rasterize(&mut framebuffer, WHITE),
+            }
+        }
+        Demo::Ellipse {
+            centre,
+            a,
+            b,
+            rotation,
+            filled,
+            antialiased,
+            arc,
+            closure,
+        } => {
+            let ellipse = ellipse(centre, a, b, filled, antialiased).with_rotation(rotation);
+            match arc {
+                Some([start, end]) => ellipse
+                    .arc(start, end)
+                    .with_closure(closure)
+                    .rasterize(&mut framebuffer, WHITE),
+                None => ellipse.rasterize(&mut framebuffer, WHITE),
+            }
+        }
+        Demo::Flag | Demo::Cube => {
+            opengl::capture(move |renderer| setup(renderer, demo), path, size);
+            return ExitCode::SUCCESS;
+        }
     }
+
+    match framebuffer.save_png(&path) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error saving {}: {}", path.display(), err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(feature = "png"))]
+fn save(_demo: Demo, _path: std::path::PathBuf, _size: [u32; 2]) -> ExitCode {
+    eprintln!("Saving images needs biopix to be built with `--features png`");
+    ExitCode::FAILURE
 }
@@ -2,6 +2,7 @@ use std::ffi::{CStr, CString};
 use std::num::NonZeroU32;
 use std::ops::Deref;
 
+use winit::dpi::PhysicalSize;
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::EventLoopBuilder;
 use winit::window::{Window, WindowBuilder};
@@ -16,6 +17,11 @@ use glutin::surface::{Surface, SurfaceAttributesBuilder, SwapInterval, WindowSur
 
 use glutin_winit::{self, DisplayBuilder};
 
+use crate::drawable::Drawable;
+use crate::framebuffer::{Color, Framebuffer};
+use crate::shader::{ShaderError, ShaderProgram};
+use crate::viewport::Viewport;
+
 pub mod gl {
     #![allow(clippy::all)]
     include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
@@ -23,14 +29,45 @@ pub mod gl {
     // pub use Gles2 as Gl;
 }
 
-pub fn init(draw_function: Option<unsafe fn(&mut Renderer) -> ()>) {
-    let event_loop = EventLoopBuilder::new().build();
+/// Opens the window and calls `setup` once the GL context is ready. `setup`
+/// adds the shapes to draw, which then stay on the GPU until the window closes.
+/// If it fails the error is printed and the window closed.
+pub fn init(setup: impl FnOnce(&mut Renderer) -> Result<(), ShaderError> + 'static) {
+    run(Box::new(setup), None, None);
+}
 
-    let window_builder = Some(
-        WindowBuilder::new()
-            .with_title("Lab Works")
-            .with_transparent(false),
+/// Renders a single frame in a window `size` pixels large, saves it to `path`
+/// and closes the window.
+#[cfg(feature = "png")]
+pub fn capture(
+    setup: impl FnOnce(&mut Renderer) -> Result<(), ShaderError> + 'static,
+    path: std::path::PathBuf,
+    size: [u32; 2],
+) {
+    run(
+        Box::new(setup),
+        Some(Box::new(move |renderer: &Renderer| {
+            if let Err(err) = renderer.read_pixels().save_png(&path) {
+                eprintln!("Error saving {}: {}", path.display(), err);
+            }
+        })),
+        Some(size),
     );
+}
+
+type SetupCallback = Box<dyn FnOnce(&mut Renderer) -> Result<(), ShaderError>>;
+type FrameCallback = Box<dyn FnOnce(&Renderer)>;
+
+fn run(setup: SetupCallback, mut on_first_frame: Option<FrameCallback>, size: Option<[u32; 2]>) {
+    let event_loop = EventLoopBuilder::new().build();
+
+    let mut window_builder = WindowBuilder::new()
+        .with_title("Lab Works")
+        .with_transparent(false);
+    if let Some([width, height]) = size {
+        window_builder = window_builder.with_inner_size(PhysicalSize::new(width, height));
+    }
+    let window_builder = Some(window_builder);
 
     let template = ConfigTemplateBuilder::new();
 
@@ -45,7 +82,10 @@ pub fn init(draw_function: Option<unsafe fn(&mut Renderer) -> ()>) {
     let raw_window_handle = window.as_ref().map(|window| window.raw_window_handle());
 
     let window_size = window.as_ref().unwrap().inner_size();
-    println!("Window Size: {} X {}", window_size.width, window_size.height);
+    println!(
+        "Window Size: {} X {}",
+        window_size.width, window_size.height
+    );
 
     let gl_display = gl_config.display();
 
@@ -67,13 +107,18 @@ pub fn init(draw_function: Option<unsafe fn(&mut Renderer) -> ()>) {
 
     let mut state = None;
     let mut renderer = None;
+    let mut setup = Some(setup);
 
     event_loop.run(move |event, window_target, control_flow| {
         control_flow.set_wait();
         match event {
             Event::Resumed => {
                 let window = window.take().unwrap_or_else(|| {
-                    let window_builder = WindowBuilder::new().with_transparent(true);
+                    let mut window_builder = WindowBuilder::new().with_transparent(true);
+                    if let Some([width, height]) = size {
+                        window_builder =
+                            window_builder.with_inner_size(PhysicalSize::new(width, height));
+                    }
                     glutin_winit::finalize_window(window_target, window_builder, &gl_config)
                         .unwrap()
                 });
@@ -86,7 +131,15 @@ pub fn init(draw_function: Option<unsafe fn(&mut Renderer) -> ()>) {
                     .make_current(&gl_window.surface)
                     .unwrap();
 
-                renderer.get_or_insert_with(|| Renderer::new(&gl_display, draw_function));
+                let window_size = gl_window.window.inner_size();
+                let renderer = renderer.get_or_insert_with(|| Renderer::new(&gl_display));
+                renderer.resize(window_size.width as i32, window_size.height as i32);
+                if let Some(setup) = setup.take() {
+                    if let Err(err) = setup(renderer) {
+                        eprintln!("{}", err);
+                        control_flow.set_exit();
+                    }
+                }
 
                 if let Err(res) = gl_window
                     .surface
@@ -104,17 +157,15 @@ pub fn init(draw_function: Option<unsafe fn(&mut Renderer) -> ()>) {
                     .is_none());
             }
             Event::WindowEvent { event, .. } => match event {
-                WindowEvent::Resized(size) => {
-                    if size.width != 0 && size.height != 0 {
-                        if let Some((gl_context, gl_window)) = &state {
-                            gl_window.surface.resize(
-                                gl_context,
-                                NonZeroU32::new(size.width).unwrap(),
-                                NonZeroU32::new(size.height).unwrap(),
-                            );
-                            let renderer = renderer.as_ref().unwrap();
-                            renderer.resize(size.width as i32, size.height as i32);
-                        }
+                WindowEvent::Resized(size) if size.width != 0 && size.height != 0 => {
+                    if let Some((gl_context, gl_window)) = &state {
+                        gl_window.surface.resize(
+                            gl_context,
+                            NonZeroU32::new(size.width).unwrap(),
+                            NonZeroU32::new(size.height).unwrap(),
+                        );
+                        let renderer = renderer.as_mut().unwrap();
+                        renderer.resize(size.width as i32, size.height as i32);
                     }
                 }
                 WindowEvent::CloseRequested => {
@@ -126,11 +177,25 @@ pub fn init(draw_function: Option<unsafe fn(&mut Renderer) -> ()>) {
                 if let Some((gl_context, gl_window)) = &state {
                     let renderer = renderer.as_mut().unwrap();
                     renderer.draw();
+
+                    // The back buffer is undefined after swapping, so read it first.
+                    if let Some(on_first_frame) = on_first_frame.take() {
+                        on_first_frame(renderer);
+                        control_flow.set_exit();
+                    }
+
                     gl_window.window.request_redraw();
 
                     gl_window.surface.swap_buffers(gl_context).unwrap();
                 }
             }
+            // `run` never returns, so nothing is dropped: free the GPU objects
+            // here while the context is still current.
+            Event::LoopDestroyed => {
+                if let (Some(renderer), Some(_)) = (renderer.take(), &state) {
+                    renderer.delete();
+                }
+            }
             _ => (),
         }
     })
@@ -162,63 +227,220 @@ impl GlWindow {
     }
 }
 
+/// Handle to a shape added to the `Renderer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ShapeId(usize);
+
+/// GPU side of a shape: created once in `Renderer::add_shape` and released
+/// by `Renderer::delete`.
+struct Mesh {
+    program: ShaderProgram,
+    vao: gl::types::GLuint,
+    vbo: gl::types::GLuint,
+    ebo: gl::types::GLuint,
+    index_count: i32,
+    primitive: gl::types::GLenum,
+}
+
+impl Mesh {
+    unsafe fn upload(&mut self, gl: &gl::Gl, viewport: &Viewport, shape: &dyn Drawable) {
+        let layout = shape.layout();
+        let vertex_data = shape.vertices(viewport);
+        let vertex_indices = shape.indices(vertex_data.len() / layout.stride);
+
+        gl.BindVertexArray(self.vao);
+
+        gl.BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+        gl.BufferData(
+            gl::ARRAY_BUFFER,
+            (vertex_data.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+            vertex_data.as_ptr() as *const _,
+            gl::STATIC_DRAW,
+        );
+
+        gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
+        gl.BufferData(
+            gl::ELEMENT_ARRAY_BUFFER,
+            (vertex_indices.len() * std::mem::size_of::<u32>()) as gl::types::GLsizeiptr,
+            vertex_indices.as_ptr() as *const _,
+            gl::STATIC_DRAW,
+        );
+
+        for attribute in &layout.attributes {
+            let location = self.program.attribute_location(gl, attribute.name);
+            if location < 0 {
+                // Declared by the layout but unused by the shader.
+                continue;
+            }
+
+            gl.VertexAttribPointer(
+                location as gl::types::GLuint,
+                attribute.size,
+                gl::FLOAT,
+                0,
+                (layout.stride * std::mem::size_of::<f32>()) as gl::types::GLsizei,
+                (attribute.offset * std::mem::size_of::<f32>()) as *const _,
+            );
+            gl.EnableVertexAttribArray(location as gl::types::GLuint);
+        }
+
+        self.index_count = vertex_indices.len() as i32;
+        self.primitive = shape.primitive();
+    }
+}
+
 pub struct Renderer {
-    pub vao: gl::types::GLuint,
-    pub vbo: gl::types::GLuint,
-    pub program: Option<gl::types::GLuint>,
+    pub viewport: Viewport,
     pub gl: gl::Gl,
-    pub draw_function: Option<unsafe fn(&mut Renderer) -> ()>,
+    shapes: Vec<(Box<dyn Drawable>, Mesh)>,
 }
 
 impl Renderer {
-    pub fn new<D: GlDisplay>(
-        gl_display: &D,
-        draw_function: Option<unsafe fn(&mut Renderer) -> ()>,
-    ) -> Self {
+    pub fn new<D: GlDisplay>(gl_display: &D) -> Self {
+        let gl = gl::Gl::load_with(|symbol| {
+            let symbol = CString::new(symbol).unwrap();
+            gl_display.get_proc_address(symbol.as_c_str()).cast()
+        });
+
+        if let Some(renderer) = get_gl_string(&gl, gl::RENDERER) {
+            println!("Running on {}", renderer.to_string_lossy());
+        }
+        if let Some(version) = get_gl_string(&gl, gl::VERSION) {
+            println!("OpenGL Version {}", version.to_string_lossy());
+        }
+
+        if let Some(shaders_version) = get_gl_string(&gl, gl::SHADING_LANGUAGE_VERSION) {
+            println!("Shaders version on {}", shaders_version.to_string_lossy());
+        }
+
+        // Anti-aliased shapes pass their coverage through the alpha channel.
         unsafe {
-            let gl = gl::Gl::load_with(|symbol| {
-                let symbol = CString::new(symbol).unwrap();
-                gl_display.get_proc_address(symbol.as_c_str()).cast()
-            });
+            gl.Enable(gl::BLEND);
+            gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
 
-            if let Some(renderer) = get_gl_string(&gl, gl::RENDERER) {
-                println!("Running on {}", renderer.to_string_lossy());
-            }
-            if let Some(version) = get_gl_string(&gl, gl::VERSION) {
-                println!("OpenGL Version {}", version.to_string_lossy());
-            }
+        Self {
+            viewport: Viewport::default(),
+            gl,
+            shapes: vec![],
+        }
+    }
 
-            if let Some(shaders_version) = get_gl_string(&gl, gl::SHADING_LANGUAGE_VERSION) {
-                println!("Shaders version on {}", shaders_version.to_string_lossy());
-            }
+    /// Compiles the shape's shaders and uploads its geometry. The shape is
+    /// drawn every frame, in the order shapes were added, until it is
+    /// replaced with `update_shape`.
+    pub fn add_shape(&mut self, shape: impl Drawable + 'static) -> Result<ShapeId, ShaderError> {
+        let shape: Box<dyn Drawable> = Box::new(shape);
+        unsafe {
+            let mut mesh = Mesh {
+                program: ShaderProgram::new(
+                    &self.gl,
+                    shape.vertex_shader(),
+                    shape.fragment_shader(),
+                )?,
+                vao: 0,
+                vbo: 0,
+                ebo: 0,
+                index_count: 0,
+                primitive: gl::POINTS,
+            };
+            self.gl.GenVertexArrays(1, &mut mesh.vao);
+            self.gl.GenBuffers(1, &mut mesh.vbo);
+            self.gl.GenBuffers(1, &mut mesh.ebo);
+
+            mesh.upload(&self.gl, &self.viewport, shape.as_ref());
+            self.shapes.push((shape, mesh));
+        }
+        Ok(ShapeId(self.shapes.len() - 1))
+    }
 
-            // let program = gl.CreateProgram();
-            //
-            // gl.LinkProgram(program);
-            // gl.UseProgram(program);
-
-            Self {
-                vao: std::mem::zeroed(),
-                vbo: std::mem::zeroed(),
-                // program,
-                program: None,
-                gl,
-                draw_function,
+    /// Replaces a shape, reusing its buffers and, when the shaders are the
+    /// same, its program. The old shape is kept if the new shaders fail to
+    /// build.
+    pub fn update_shape(
+        &mut self,
+        id: ShapeId,
+        shape: impl Drawable + 'static,
+    ) -> Result<(), ShaderError> {
+        let shape: Box<dyn Drawable> = Box::new(shape);
+        let (old_shape, mesh) = &mut self.shapes[id.0];
+        unsafe {
+            if old_shape.vertex_shader() != shape.vertex_shader()
+                || old_shape.fragment_shader() != shape.fragment_shader()
+            {
+                let program =
+                    ShaderProgram::new(&self.gl, shape.vertex_shader(), shape.fragment_shader())?;
+                std::mem::replace(&mut mesh.program, program).delete(&self.gl);
             }
+            mesh.upload(&self.gl, &self.viewport, shape.as_ref());
         }
+        *old_shape = shape;
+        Ok(())
     }
 
     pub fn draw(&mut self) {
-        if self.draw_function.is_some() {
-            unsafe {
-                self.draw_function.unwrap()(self);
+        unsafe {
+            self.gl.ClearColor(0.1, 0.1, 0.1, 0.9);
+            self.gl.Clear(gl::COLOR_BUFFER_BIT);
+
+            for (shape, mesh) in &self.shapes {
+                mesh.program.use_program(&self.gl);
+                shape.set_uniforms(&self.gl, &mesh.program, &self.viewport);
+
+                self.gl.BindVertexArray(mesh.vao);
+                self.gl.DrawElements(
+                    mesh.primitive,
+                    mesh.index_count,
+                    gl::UNSIGNED_INT,
+                    std::ptr::null(),
+                );
             }
         }
     }
 
-    pub fn resize(&self, width: i32, height: i32) {
+    /// Tracks the new surface size. Geometry in world coordinates depends on
+    /// the viewport, so every shape is uploaded again.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        self.viewport.resize(width as u32, height as u32);
         unsafe {
             self.gl.Viewport(0, 0, width, height);
+
+            for (shape, mesh) in &mut self.shapes {
+                mesh.upload(&self.gl, &self.viewport, shape.as_ref());
+            }
+        }
+    }
+
+    /// Reads back the current contents of the colour buffer.
+    pub fn read_pixels(&self) -> Framebuffer {
+        let [width, height] = self.viewport.surface;
+        let mut pixels: Vec<Color> = vec![[0; 4]; width as usize * height as usize];
+        unsafe {
+            self.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+            self.gl.ReadPixels(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr().cast(),
+            );
+        }
+        // OpenGL returns rows bottom up, the same order the framebuffer stores.
+        Framebuffer::from_pixels(width, height, pixels)
+    }
+
+    /// Frees every shape's program and buffers. The context they were created
+    /// in has to be current.
+    pub fn delete(self) {
+        unsafe {
+            for (_, mesh) in self.shapes {
+                mesh.program.delete(&self.gl);
+                self.gl.DeleteBuffers(1, &mesh.vbo);
+                self.gl.DeleteBuffers(1, &mesh.ebo);
+                self.gl.DeleteVertexArrays(1, &mesh.vao);
+            }
         }
     }
 }
@@ -231,36 +453,9 @@ impl Deref for Renderer {
     }
 }
 
-impl Drop for Renderer {
-    fn drop(&mut self) {
-        unsafe {
-            if let Some(program) = self.program {
-                self.gl.DeleteProgram(program);
-            }
-            self.gl.DeleteBuffers(1, &self.vbo);
-            self.gl.DeleteVertexArrays(1, &self.vao);
-        }
-    }
-}
-
 fn get_gl_string(gl: &gl::Gl, variant: gl::types::GLenum) -> Option<&'static CStr> {
     unsafe {
         let s = gl.GetString(variant);
         (!s.is_null()).then(|| CStr::from_ptr(s.cast()))
     }
 }
-pub unsafe fn create_shader(
-    gl: &gl::Gl,
-    shader: gl::types::GLenum,
-    source: &[u8],
-) -> gl::types::GLuint {
-    let shader = gl.CreateShader(shader);
-    gl.ShaderSource(
-        shader,
-        1,
-        [source.as_ptr().cast()].as_ptr(),
-        std::ptr::null(),
-    );
-    gl.CompileShader(shader);
-    return shader;
-}
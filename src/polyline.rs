@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use crate::dash::Dash;
+use crate::drawable::{Drawable, VertexLayout};
+use crate::framebuffer::{Canvas, Color, Points, WHITE};
+use crate::line::{Line, LineAlgorithm};
+use crate::opengl::gl;
+use crate::stroke::{Stroke, StrokedPath};
+use crate::transform::transform_points;
+use crate::viewport::Viewport;
+
+/// Connected line segments through an ordered list of vertices. A polygon is
+/// a closed polyline, with an extra edge from the last vertex back to the
+/// first.
+pub struct Polyline {
+    points: Vec<[f32; 2]>,
+    coverage: Vec<f32>,
+    vertices: Vec<[f32; 2]>,
+    closed: bool,
+    algo: LineAlgorithm,
+    dash: Option<Dash>,
+}
+
+impl Polyline {
+    pub fn new(vertices: &[[f32; 2]], algo: LineAlgorithm) -> Self {
+        Self::with_closed(vertices, false, algo)
+    }
+
+    pub fn polygon(vertices: &[[f32; 2]], algo: LineAlgorithm) -> Self {
+        Self::with_closed(vertices, true, algo)
+    }
+
+    fn with_closed(vertices: &[[f32; 2]], closed: bool, algo: LineAlgorithm) -> Self {
+        let mut polyline = Self {
+            vertices: vertices.to_vec(),
+            closed,
+            algo,
+            dash: None,
+            points: vec![],
+            coverage: vec![],
+        };
+        polyline.collect_points();
+        polyline
+    }
+
+    /// Dashes the whole outline, the pattern carrying on from one edge to the
+    /// next instead of restarting at every vertex.
+    pub fn with_dash(mut self, dash: Dash) -> Self {
+        self.dash = Some(dash);
+        self.collect_points();
+        self
+    }
+
+    pub fn vertices(&self) -> &[[f32; 2]] {
+        &self.vertices
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// The edges as lines, in order.
+    pub fn edges(&self) -> Vec<Line> {
+        let count = match self.vertices.len() {
+            0 => 0,
+            1 => 1,
+            n if self.closed => n,
+            n => n - 1,
+        };
+
+        let mut dash = self.dash.clone();
+        (0..count)
+            .map(|i| {
+                let from = self.vertices[i];
+                let to = self.vertices[(i + 1) % self.vertices.len()];
+                let line = Line::new(from, to, self.algo);
+                let line = match dash.take() {
+                    Some(pattern) => line.with_dash(pattern),
+                    None => line,
+                };
+                dash = line.next_dash();
+                line
+            })
+            .collect()
+    }
+
+    fn collect_points(&mut self) {
+        let mut points = Points::default();
+        self.rasterize(&mut points, WHITE);
+        self.points = points.points;
+        self.coverage = points.coverage;
+    }
+
+    /// Plots every edge with the polyline's algorithm. Pixels shared by
+    /// neighbouring edges, the vertices above all, are plotted once; for the
+    /// anti-aliased algorithm they keep the larger coverage of the two, so a
+    /// pixel between edges is never more than fully covered.
+    pub fn rasterize(&self, canvas: &mut impl Canvas, color: Color) {
+        let mut edges = Points::default();
+        for edge in self.edges() {
+            edge.rasterize(&mut edges, color);
+        }
+
+        let mut pixels: Vec<([i32; 2], f32)> = vec![];
+        let mut seen: HashMap<[i32; 2], usize> = HashMap::new();
+        for (&[x, y], &coverage) in edges.points.iter().zip(&edges.coverage) {
+            let pixel = [x as i32, y as i32];
+            match seen.get(&pixel) {
+                Some(&i) => {
+                    let merged = &mut pixels[i].1;
+                    *merged = merged.max(coverage);
+                }
+                None => {
+                    seen.insert(pixel, pixels.len());
+                    pixels.push((pixel, coverage));
+                }
+            }
+        }
+
+        for ([x, y], coverage) in pixels {
+            if coverage < 1.0 {
+                canvas.blend(x, y, color, coverage);
+            } else {
+                canvas.plot(x, y, color);
+            }
+        }
+    }
+
+    pub fn points(&self) -> &[[f32; 2]] {
+        &self.points
+    }
+
+    pub fn coverage(&self) -> &[f32] {
+        &self.coverage
+    }
+
+    /// Vertices laid out as `VertexLayout::position_color_alpha(3)`, the same
+    /// as `Line::get_normalized_coordinate`.
+    pub fn get_normalized_coordinate(&self, viewport: &Viewport) -> Vec<f32> {
+        let window_to_ndc = viewport.window_to_ndc();
+        self.points
+            .iter()
+            .zip(&self.coverage)
+            .flat_map(|(&item, &coverage)| {
+                let [x, y] = window_to_ndc.apply(item);
+                [
+                    x, y, 0.0, // Z
+                    1.0, // R
+                    1.0, // G
+                    1.0, // B
+                    coverage,
+                ]
+            })
+            .collect()
+    }
+
+    /// Applies a row-major 2D homogeneous matrix, or a `Transform2D`, to the
+    /// rasterized points.
+    /// The vertices used by `rasterize` are left untouched.
+    pub fn transform(&mut self, matrix: impl Into<[[f32; 3]; 3]>) {
+        transform_points(&mut self.points, matrix.into());
+    }
+
+    /// The outline drawn `stroke.width` wide, with `stroke.join` at every
+    /// vertex.
+    pub fn stroke(&self, stroke: &Stroke) -> StrokedPath {
+        StrokedPath::new(&self.vertices, self.closed, stroke)
+    }
+}
+
+impl Drawable for Polyline {
+    fn vertices(&self, viewport: &Viewport) -> Vec<f32> {
+        self.get_normalized_coordinate(viewport)
+    }
+
+    fn layout(&self) -> VertexLayout {
+        VertexLayout::position_color_alpha(3)
+    }
+
+    fn primitive(&self) -> gl::types::GLenum {
+        gl::POINTS
+    }
+}
+
+#[test]
+fn polyline_test() {
+    let unique = |points: &[[f32; 2]]| {
+        let mut sorted: Vec<_> = points.iter().map(|&[x, y]| [x as i32, y as i32]).collect();
+        sorted.sort_unstable();
+        sorted.dedup();
+        sorted.len() == points.len()
+    };
+
+    let vertices = [[0.0, 0.0], [10.0, 0.0], [10.0, 6.0], [0.0, 6.0]];
+    for algo in [LineAlgorithm::Bresenham, LineAlgorithm::Wu] {
+        let open = Polyline::new(&vertices, algo);
+        let closed = Polyline::polygon(&vertices, algo);
+        assert!(unique(open.points()) && unique(closed.points()), "{algo:?}");
+        assert!(closed.coverage().iter().all(|&c| c > 0.0 && c <= 1.0));
+    }
+
+    // Where edges meet, the vertices of a polygon above all, a pixel is as
+    // covered as the edge nearest to it and never more than fully, so acute
+    // vertices do not saturate pixels the lines barely touch.
+    let spike = Polyline::polygon(&[[0.0, 0.0], [20.0, 3.0], [0.0, 5.0]], LineAlgorithm::Wu);
+    let mut nearest: HashMap<[i32; 2], f32> = HashMap::new();
+    for edge in spike.edges() {
+        for (&[x, y], &c) in edge.points().iter().zip(edge.coverage()) {
+            let merged = nearest.entry([x as i32, y as i32]).or_default();
+            *merged = merged.max(c);
+        }
+    }
+    for (&[x, y], &c) in spike.points().iter().zip(spike.coverage()) {
+        assert!(c <= 1.0, "{x},{y}: {c}");
+        assert_eq!(nearest[&[x as i32, y as i32]], c, "{x},{y}");
+    }
+
+    // Every pixel of the rectangle's outline once, and no more.
+    let open = Polyline::new(&vertices, LineAlgorithm::Bresenham);
+    assert_eq!(open.points().len(), 10 + 6 + 10 + 1);
+    let closed = Polyline::polygon(&vertices, LineAlgorithm::Bresenham);
+    assert_eq!(closed.points().len(), 2 * (10 + 6));
+
+    // The dashes carry on round corners, so two collinear edges dash the
+    // same as one line.
+    let dash = Dash::new(&[4, 3]).with_phase(2);
+    let bent = Polyline::new(&[[0.0, 0.0], [5.0, 0.0], [17.0, 0.0]], LineAlgorithm::DDA)
+        .with_dash(dash.clone());
+    let straight = Line::new([0.0, 0.0], [17.0, 0.0], LineAlgorithm::DDA).with_dash(dash);
+    assert_eq!(bent.points(), straight.points());
+}
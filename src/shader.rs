@@ -0,0 +1,306 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fmt;
+
+use crate::opengl::gl;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+impl ShaderStage {
+    fn gl_enum(self) -> gl::types::GLenum {
+        match self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+        }
+    }
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShaderError {
+    /// The source is missing its terminating nul byte.
+    NotNulTerminated {
+        stage: ShaderStage,
+    },
+    Compile {
+        stage: ShaderStage,
+        log: String,
+        /// Line number and text of the first line the driver complained
+        /// about, when the log could be parsed.
+        line: Option<(usize, String)>,
+    },
+    Link {
+        log: String,
+    },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderError::NotNulTerminated { stage } => {
+                write!(f, "{} shader source is not nul terminated", stage)
+            }
+            ShaderError::Compile { stage, log, line } => {
+                write!(f, "{} shader failed to compile", stage)?;
+                if let Some((number, text)) = line {
+                    write!(f, " at line {}: {}", number, text.trim())?;
+                }
+                write!(f, "\n{}", log.trim_end())
+            }
+            ShaderError::Link { log } => {
+                write!(f, "shader program failed to link\n{}", log.trim_end())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// A linked program along with the uniform and attribute locations looked up
+/// so far.
+///
+/// Like the rest of the renderer's GL objects it does not hold on to the
+/// context, so it has to be released with [`ShaderProgram::delete`].
+#[derive(Debug)]
+pub struct ShaderProgram {
+    id: gl::types::GLuint,
+    uniforms: RefCell<HashMap<CString, gl::types::GLint>>,
+    attributes: RefCell<HashMap<CString, gl::types::GLint>>,
+}
+
+impl ShaderProgram {
+    /// Compiles and links the nul terminated shader sources.
+    ///
+    /// # Safety
+    ///
+    /// The context `gl` was loaded from must be current on the calling thread.
+    pub unsafe fn new(
+        gl: &gl::Gl,
+        vertex_source: &[u8],
+        fragment_source: &[u8],
+    ) -> Result<Self, ShaderError> {
+        let vertex_shader = compile(gl, ShaderStage::Vertex, vertex_source)?;
+        let fragment_shader = match compile(gl, ShaderStage::Fragment, fragment_source) {
+            Ok(shader) => shader,
+            Err(err) => {
+                gl.DeleteShader(vertex_shader);
+                return Err(err);
+            }
+        };
+
+        let id = gl.CreateProgram();
+        gl.AttachShader(id, vertex_shader);
+        gl.AttachShader(id, fragment_shader);
+        gl.LinkProgram(id);
+
+        // The shader objects are only needed until the program is linked.
+        gl.DetachShader(id, vertex_shader);
+        gl.DetachShader(id, fragment_shader);
+        gl.DeleteShader(vertex_shader);
+        gl.DeleteShader(fragment_shader);
+
+        let mut status = 0;
+        gl.GetProgramiv(id, gl::LINK_STATUS, &mut status);
+        if status == 0 {
+            let log = info_log(gl, id, gl::Gl::GetProgramiv, gl::Gl::GetProgramInfoLog);
+            gl.DeleteProgram(id);
+            return Err(ShaderError::Link { log });
+        }
+
+        Ok(Self {
+            id,
+            uniforms: RefCell::default(),
+            attributes: RefCell::default(),
+        })
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    /// # Safety
+    ///
+    /// The context `gl` was loaded from must be current on the calling thread.
+    pub unsafe fn use_program(&self, gl: &gl::Gl) {
+        gl.UseProgram(self.id);
+    }
+
+    /// Location of the uniform `name`, or -1 if the program has no such active
+    /// uniform.
+    ///
+    /// # Safety
+    ///
+    /// The context `gl` was loaded from must be current on the calling thread.
+    pub unsafe fn uniform_location(&self, gl: &gl::Gl, name: &CStr) -> gl::types::GLint {
+        *self
+            .uniforms
+            .borrow_mut()
+            .entry(name.to_owned())
+            .or_insert_with(|| gl.GetUniformLocation(self.id, name.as_ptr()))
+    }
+
+    /// Location of the attribute `name`, or -1 if the program has no such
+    /// active attribute.
+    ///
+    /// # Safety
+    ///
+    /// The context `gl` was loaded from must be current on the calling thread.
+    pub unsafe fn attribute_location(&self, gl: &gl::Gl, name: &CStr) -> gl::types::GLint {
+        *self
+            .attributes
+            .borrow_mut()
+            .entry(name.to_owned())
+            .or_insert_with(|| gl.GetAttribLocation(self.id, name.as_ptr()))
+    }
+
+    /// # Safety
+    ///
+    /// The context `gl` was loaded from must be current on the calling thread.
+    pub unsafe fn delete(self, gl: &gl::Gl) {
+        gl.DeleteProgram(self.id);
+    }
+}
+
+unsafe fn compile(
+    gl: &gl::Gl,
+    stage: ShaderStage,
+    source: &[u8],
+) -> Result<gl::types::GLuint, ShaderError> {
+    let source =
+        CStr::from_bytes_until_nul(source).map_err(|_| ShaderError::NotNulTerminated { stage })?;
+
+    let shader = gl.CreateShader(stage.gl_enum());
+    gl.ShaderSource(shader, 1, [source.as_ptr()].as_ptr(), std::ptr::null());
+    gl.CompileShader(shader);
+
+    let mut status = 0;
+    gl.GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+    if status == 0 {
+        let log = info_log(gl, shader, gl::Gl::GetShaderiv, gl::Gl::GetShaderInfoLog);
+        gl.DeleteShader(shader);
+
+        let source = source.to_string_lossy();
+        let line = error_line(&log).and_then(|number| {
+            let text = source.lines().nth(number.checked_sub(1)?)?;
+            Some((number, text.to_owned()))
+        });
+
+        return Err(ShaderError::Compile { stage, log, line });
+    }
+
+    Ok(shader)
+}
+
+type GetIv = unsafe fn(&gl::Gl, gl::types::GLuint, gl::types::GLenum, *mut gl::types::GLint);
+type GetInfoLog = unsafe fn(
+    &gl::Gl,
+    gl::types::GLuint,
+    gl::types::GLsizei,
+    *mut gl::types::GLsizei,
+    *mut gl::types::GLchar,
+);
+
+/// Shader and program info logs are fetched the same way with different
+/// entry points.
+unsafe fn info_log(
+    gl: &gl::Gl,
+    object: gl::types::GLuint,
+    get_iv: GetIv,
+    get_info_log: GetInfoLog,
+) -> String {
+    let mut length = 0;
+    get_iv(gl, object, gl::INFO_LOG_LENGTH, &mut length);
+
+    let mut log = vec![0u8; length.max(1) as usize];
+    let mut written = 0;
+    get_info_log(gl, object, length, &mut written, log.as_mut_ptr().cast());
+    log.truncate(written as usize);
+
+    String::from_utf8_lossy(&log).into_owned()
+}
+
+/// Line number of the first error in a compile log. Drivers disagree on the
+/// format, but all of them prefix messages with `<source>:<line>` or
+/// `<source>(<line>)`:
+///
+/// - Mesa: `0:12(5): error: ...`
+/// - NVIDIA: `0(12) : error C0000: ...`
+/// - ANGLE, Apple and Intel on Windows: `ERROR: 0:12: ...`
+fn error_line(log: &str) -> Option<usize> {
+    log.lines().find_map(|message| {
+        let bytes = message.as_bytes();
+        (0..bytes.len()).find_map(|start| {
+            if start > 0 && bytes[start - 1].is_ascii_alphanumeric() {
+                return None;
+            }
+
+            let source_end = start
+                + bytes[start..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+            if source_end == start {
+                return None;
+            }
+
+            let open = *bytes.get(source_end)?;
+            if open != b':' && open != b'(' {
+                return None;
+            }
+
+            let line_start = source_end + 1;
+            let line_end = line_start
+                + bytes[line_start..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+            let close = *bytes.get(line_end)?;
+            let closed =
+                (open == b':' && matches!(close, b':' | b'(')) || (open == b'(' && close == b')');
+
+            if line_end > line_start && closed {
+                message[line_start..line_end].parse().ok()
+            } else {
+                None
+            }
+        })
+    })
+}
+
+#[test]
+fn error_line_test() {
+    assert_eq!(error_line("0:12(5): error: `positon' undeclared"), Some(12));
+    assert_eq!(
+        error_line("0(7) : error C1008: undefined variable \"positon\""),
+        Some(7)
+    );
+    assert_eq!(
+        error_line("ERROR: 0:3: 'positon' : undeclared identifier\nERROR: 1 compilation errors."),
+        Some(3)
+    );
+    assert_eq!(error_line("error: linking failed"), None);
+
+    let err = ShaderError::Compile {
+        stage: ShaderStage::Vertex,
+        log: "0:3(5): error: `positon' undeclared\n".to_owned(),
+        line: Some((3, "    gl_Position = vec4(positon, 1.0);".to_owned())),
+    };
+    assert_eq!(
+        err.to_string(),
+        "vertex shader failed to compile at line 3: gl_Position = vec4(positon, 1.0);\n\
+         0:3(5): error: `positon' undeclared"
+    );
+}
@@ -0,0 +1,482 @@
+use std::collections::BTreeMap;
+use std::f32::consts::PI;
+
+use crate::drawable::{Drawable, VertexLayout};
+use crate::framebuffer::{Canvas, Color, Span};
+use crate::opengl::gl;
+use crate::viewport::Viewport;
+
+/// How the open ends of a stroke are finished.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineCap {
+    /// Square end, flush with the endpoint.
+    #[default]
+    Butt,
+    /// Half circle around the endpoint.
+    Round,
+    /// Square end, half the width past the endpoint.
+    Square,
+}
+
+/// How the outside corner between two segments of a stroke is filled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineJoin {
+    /// Extends both edges until they meet, falling back to `Bevel` for
+    /// corners sharper than the stroke's `miter_limit`.
+    #[default]
+    Miter,
+    /// Cuts the corner off straight.
+    Bevel,
+    /// Rounds the corner with a circle around the vertex.
+    Round,
+}
+
+impl std::str::FromStr for LineCap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "butt" => Ok(LineCap::Butt),
+            "round" => Ok(LineCap::Round),
+            "square" => Ok(LineCap::Square),
+            _ => Err(format!("unknown line cap `{}`", s)),
+        }
+    }
+}
+
+impl std::str::FromStr for LineJoin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "miter" => Ok(LineJoin::Miter),
+            "bevel" => Ok(LineJoin::Bevel),
+            "round" => Ok(LineJoin::Round),
+            _ => Err(format!("unknown line join `{}`", s)),
+        }
+    }
+}
+
+/// Width and end and corner styles of a thick line, in world units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    /// Longest miter allowed, as a multiple of the width, the same limit
+    /// SVG and PostScript use.
+    pub miter_limit: f32,
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4.0,
+        }
+    }
+}
+
+impl Stroke {
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            ..Self::default()
+        }
+    }
+
+    /// Triangles covering the stroke along `path`, three vertices each.
+    /// A `closed` path joins its last point back to the first and has no
+    /// caps, even when it only has two points.
+    ///
+    /// Segments, caps and joins are separate triangles that overlap along the
+    /// inside of corners, which makes no difference when they are all filled
+    /// with one colour.
+    pub fn triangles(&self, path: &[[f32; 2]], closed: bool) -> Vec<[f32; 2]> {
+        let mut points = path.to_vec();
+        points.dedup();
+        if closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+
+        let half = self.width / 2.0;
+        let mut triangles = vec![];
+        if half <= 0.0 || points.is_empty() {
+            return triangles;
+        }
+
+        if points.len() == 1 {
+            // A single point only shows up through its caps.
+            let p = points[0];
+            match self.cap {
+                LineCap::Butt => {}
+                LineCap::Round => fan(&mut triangles, p, half, 0.0, 2.0 * PI),
+                LineCap::Square => quad(
+                    &mut triangles,
+                    [
+                        [p[0] - half, p[1] - half],
+                        [p[0] + half, p[1] - half],
+                        [p[0] + half, p[1] + half],
+                        [p[0] - half, p[1] + half],
+                    ],
+                ),
+            }
+            return triangles;
+        }
+
+        // Closed through only two points, the way back runs over the way
+        // there, so the stroke is the one segment between them.
+        let ring = closed && points.len() > 2;
+        let segments = if ring { points.len() } else { points.len() - 1 };
+        let segment = |i: usize| (points[i], points[(i + 1) % points.len()]);
+
+        for i in 0..segments {
+            let (p, q) = segment(i);
+            let n = scale(normal(p, q), half);
+            quad(&mut triangles, [add(p, n), sub(p, n), sub(q, n), add(q, n)]);
+        }
+
+        let joins = if ring { 0..segments } else { 1..segments };
+        for i in joins {
+            let (p, v) = segment((i + segments - 1) % segments);
+            let (_, q) = segment(i);
+            self.join(&mut triangles, p, v, q);
+        }
+
+        if !closed {
+            let (p, q) = segment(0);
+            self.cap(&mut triangles, q, p);
+            let (p, q) = segment(segments - 1);
+            self.cap(&mut triangles, p, q);
+        }
+
+        triangles
+    }
+
+    /// Pixels whose centres lie inside the stroke, merged into one set of
+    /// non-overlapping spans per row, so every pixel is plotted once.
+    pub fn spans(&self, path: &[[f32; 2]], closed: bool) -> Vec<Span> {
+        let mut rows: BTreeMap<i32, Vec<(i32, i32)>> = BTreeMap::new();
+        for triangle in self.triangles(path, closed).chunks_exact(3) {
+            triangle_spans([triangle[0], triangle[1], triangle[2]], &mut rows);
+        }
+
+        let mut spans = vec![];
+        for (y, mut runs) in rows {
+            runs.sort_unstable();
+            let mut runs = runs.into_iter();
+            let Some((mut x0, mut x1)) = runs.next() else {
+                continue;
+            };
+            for (start, end) in runs {
+                if start <= x1 + 1 {
+                    x1 = x1.max(end);
+                } else {
+                    spans.push(Span { y, x0, x1 });
+                    (x0, x1) = (start, end);
+                }
+            }
+            spans.push(Span { y, x0, x1 });
+        }
+        spans
+    }
+
+    /// Adds the cap at `end` of the segment from `from`.
+    fn cap(&self, triangles: &mut Vec<[f32; 2]>, from: [f32; 2], end: [f32; 2]) {
+        let half = self.width / 2.0;
+        let n = scale(normal(from, end), half);
+        match self.cap {
+            LineCap::Butt => {}
+            LineCap::Round => {
+                let start = n[1].atan2(n[0]);
+                fan(triangles, end, half, start - PI, start);
+            }
+            LineCap::Square => {
+                let d = [n[1], -n[0]];
+                quad(
+                    triangles,
+                    [
+                        add(end, n),
+                        sub(end, n),
+                        add(sub(end, n), d),
+                        add(add(end, n), d),
+                    ],
+                );
+            }
+        }
+    }
+
+    /// Fills the outside of the corner at `v` between the segments `p`-`v`
+    /// and `v`-`q`.
+    fn join(&self, triangles: &mut Vec<[f32; 2]>, p: [f32; 2], v: [f32; 2], q: [f32; 2]) {
+        let half = self.width / 2.0;
+        let turn = cross(sub(v, p), sub(q, v));
+        if turn == 0.0 && dot(sub(v, p), sub(q, v)) > 0.0 {
+            // Straight on, the segments already meet flush.
+            return;
+        }
+
+        // The outside of a left turn is on the right of both segments.
+        let side = if turn > 0.0 { -half } else { half };
+        let before = scale(normal(p, v), side);
+        let after = scale(normal(v, q), side);
+
+        match self.join {
+            LineJoin::Round => {
+                let start = before[1].atan2(before[0]);
+                let mut end = after[1].atan2(after[0]);
+                // The normals turn the same way as the path, the short way
+                // round.
+                if turn > 0.0 {
+                    while end < start {
+                        end += 2.0 * PI;
+                    }
+                } else {
+                    while end > start {
+                        end -= 2.0 * PI;
+                    }
+                }
+                fan(triangles, v, half, start, end);
+            }
+            LineJoin::Miter | LineJoin::Bevel => {
+                triangles.extend([v, add(v, before), add(v, after)]);
+
+                let bisector = add(before, after);
+                let length = dot(bisector, bisector).sqrt();
+                if self.join == LineJoin::Bevel || length == 0.0 {
+                    return;
+                }
+                // Distance from the vertex to the tip over the half width,
+                // which is also the miter length over the width.
+                let miter = 2.0 * half / length;
+                if miter > self.miter_limit {
+                    return;
+                }
+                let tip = add(v, scale(bisector, half * miter / length));
+                triangles.extend([add(v, before), tip, add(v, after)]);
+            }
+        }
+    }
+}
+
+/// A stroked path ready to be drawn with `gl::TRIANGLES` or filled into a
+/// canvas.
+pub struct StrokedPath {
+    triangles: Vec<[f32; 2]>,
+    spans: Vec<Span>,
+}
+
+impl StrokedPath {
+    pub fn new(path: &[[f32; 2]], closed: bool, stroke: &Stroke) -> Self {
+        Self {
+            triangles: stroke.triangles(path, closed),
+            spans: stroke.spans(path, closed),
+        }
+    }
+
+    pub fn triangles(&self) -> &[[f32; 2]] {
+        &self.triangles
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    pub fn rasterize(&self, canvas: &mut impl Canvas, color: Color) {
+        for &span in &self.spans {
+            canvas.fill_span(span, color);
+        }
+    }
+}
+
+impl Drawable for StrokedPath {
+    fn vertices(&self, viewport: &Viewport) -> Vec<f32> {
+        let window_to_ndc = viewport.window_to_ndc();
+        self.triangles
+            .iter()
+            .flat_map(|&item| {
+                let [x, y] = window_to_ndc.apply(item);
+                [
+                    x, y, 0.0, // Z
+                    1.0, // R
+                    1.0, // G
+                    1.0, // B
+                ]
+            })
+            .collect()
+    }
+
+    fn layout(&self) -> VertexLayout {
+        VertexLayout::position_color(3)
+    }
+
+    fn primitive(&self) -> gl::types::GLenum {
+        gl::TRIANGLES
+    }
+}
+
+/// Rows of `triangle` whose pixel centres it covers. Pixels are half open,
+/// taking in the left and bottom edges but not the right and top ones, so
+/// triangles sharing an edge never both claim a pixel on it.
+fn triangle_spans(triangle: [[f32; 2]; 3], rows: &mut BTreeMap<i32, Vec<(i32, i32)>>) {
+    let ymin = triangle.iter().map(|p| p[1]).fold(f32::INFINITY, f32::min);
+    let ymax = triangle
+        .iter()
+        .map(|p| p[1])
+        .fold(f32::NEG_INFINITY, f32::max);
+
+    let mut y = ymin.ceil() as i32;
+    while (y as f32) < ymax {
+        let row = y as f32;
+        let mut left = f32::INFINITY;
+        let mut right = f32::NEG_INFINITY;
+        for i in 0..3 {
+            let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+            if (a[1] <= row && row <= b[1]) || (b[1] <= row && row <= a[1]) {
+                let x = if a[1] == b[1] {
+                    left = left.min(a[0].min(b[0]));
+                    a[0].max(b[0])
+                } else {
+                    a[0] + (row - a[1]) * (b[0] - a[0]) / (b[1] - a[1])
+                };
+                left = left.min(x);
+                right = right.max(x);
+            }
+        }
+
+        let (x0, x1) = (left.ceil() as i32, right.ceil() as i32 - 1);
+        if x0 <= x1 {
+            rows.entry(y).or_default().push((x0, x1));
+        }
+        y += 1;
+    }
+}
+
+/// Two triangles covering the quad `a`, `b`, `c`, `d`.
+fn quad(triangles: &mut Vec<[f32; 2]>, [a, b, c, d]: [[f32; 2]; 4]) {
+    triangles.extend([a, b, c, a, c, d]);
+}
+
+/// Triangle fan around `centre` approximating the arc from angle `start` to
+/// `end`, fine enough to stay within a quarter pixel of the true circle.
+fn fan(triangles: &mut Vec<[f32; 2]>, centre: [f32; 2], radius: f32, start: f32, end: f32) {
+    let step = if radius > 0.25 {
+        2.0 * (1.0 - 0.25 / radius).acos()
+    } else {
+        PI / 2.0
+    };
+    let count = ((end - start).abs() / step).ceil().max(1.0) as usize;
+    let point = |i: usize| {
+        let angle = start + (end - start) * i as f32 / count as f32;
+        [
+            centre[0] + radius * angle.cos(),
+            centre[1] + radius * angle.sin(),
+        ]
+    };
+    for i in 0..count {
+        triangles.extend([centre, point(i), point(i + 1)]);
+    }
+}
+
+fn add(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn scale(a: [f32; 2], s: f32) -> [f32; 2] {
+    [a[0] * s, a[1] * s]
+}
+
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn cross(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+/// Unit normal to the left of the direction from `p` to `q`.
+fn normal(p: [f32; 2], q: [f32; 2]) -> [f32; 2] {
+    let d = sub(q, p);
+    let length = dot(d, d).sqrt();
+    [-d[1] / length, d[0] / length]
+}
+
+#[test]
+fn stroke_test() {
+    let count = |spans: &[Span]| -> i32 { spans.iter().map(|s| s.x1 - s.x0 + 1).sum() };
+    let line = [[0.0, 0.0], [10.0, 0.0]];
+
+    let butt = Stroke::new(4.0).spans(&line, false);
+    assert_eq!(
+        butt,
+        (-2..2)
+            .map(|y| Span { y, x0: 0, x1: 9 })
+            .collect::<Vec<_>>()
+    );
+
+    let square = Stroke {
+        cap: LineCap::Square,
+        ..Stroke::new(4.0)
+    };
+    assert!(square
+        .spans(&line, false)
+        .iter()
+        .all(|s| s.x0 == -2 && s.x1 == 11));
+
+    let round = Stroke {
+        cap: LineCap::Round,
+        ..Stroke::new(4.0)
+    };
+    let round = count(&round.spans(&line, false));
+    assert!(count(&butt) < round && round < 4 * 14);
+
+    // A closed square outline has no caps and its mitred corners fill the
+    // frame exactly.
+    let frame = [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]];
+    let spans = Stroke::new(2.0).spans(&frame, true);
+    assert_eq!(count(&spans), 12 * 12 - 8 * 8);
+    assert_eq!(square.spans(&line, true), butt);
+    assert_eq!(
+        spans.first(),
+        Some(&Span {
+            y: -1,
+            x0: -1,
+            x1: 10
+        })
+    );
+
+    let corner = [[0.0, 0.0], [20.0, 0.0], [20.0, 20.0]];
+    let join = |join: LineJoin| {
+        let stroke = Stroke {
+            join,
+            ..Stroke::new(6.0)
+        };
+        count(&stroke.spans(&corner, false))
+    };
+    assert!(join(LineJoin::Bevel) < join(LineJoin::Round));
+    assert!(join(LineJoin::Round) < join(LineJoin::Miter));
+
+    // Past the miter limit the corner is bevelled instead.
+    let spike = [[0.0, 0.0], [20.0, 0.0], [0.0, 2.0]];
+    let miter = Stroke::new(2.0);
+    let bevel = Stroke {
+        join: LineJoin::Bevel,
+        ..miter
+    };
+    assert_eq!(miter.spans(&spike, false), bevel.spans(&spike, false));
+
+    // A single point only shows up with caps that reach past it.
+    let dot = [[1.0, 1.0], [1.0, 1.0]];
+    assert!(Stroke::new(4.0).triangles(&dot, false).is_empty());
+    let round = Stroke {
+        cap: LineCap::Round,
+        ..Stroke::new(4.0)
+    };
+    assert!(!round.triangles(&dot, false).is_empty());
+}
@@ -0,0 +1,325 @@
+use std::ops::Mul;
+
+use ndarray::prelude::*;
+
+use crate::opengl::gl;
+
+/// Applies a row-major 2D homogeneous matrix to every point, treating each
+/// point as the column vector `[x, y, 1]`.
+pub fn transform_points(points: &mut [[f32; 2]], matrix: [[f32; 3]; 3]) {
+    let transformation_matrix = arr2(&matrix);
+
+    let mut homogeneous = Array2::<f32>::ones((3, points.len()));
+    for (i, point) in points.iter().enumerate() {
+        homogeneous[[0, i]] = point[0];
+        homogeneous[[1, i]] = point[1];
+    }
+
+    let transformed = transformation_matrix.dot(&homogeneous);
+
+    for (point, column) in points.iter_mut().zip(transformed.columns()) {
+        *point = [column[0] / column[2], column[1] / column[2]];
+    }
+}
+
+/// A 2D affine transformation stored as a row-major homogeneous matrix.
+///
+/// Angles are in radians and counter-clockwise. Transformations compose with
+/// [`Transform2D::then`] in the order they are applied:
+///
+/// ```
+/// use biopix::transform::Transform2D;
+///
+/// let t = Transform2D::scale(2.0, 2.0).then(Transform2D::translate(1.0, 0.0));
+/// assert_eq!(t.apply([1.0, 1.0]), [3.0, 2.0]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2D {
+    matrix: [[f32; 3]; 3],
+}
+
+impl Transform2D {
+    #[rustfmt::skip]
+    pub const IDENTITY: Self = Self::from_matrix([
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+    ]);
+
+    pub const fn from_matrix(matrix: [[f32; 3]; 3]) -> Self {
+        Self { matrix }
+    }
+
+    pub fn matrix(&self) -> [[f32; 3]; 3] {
+        self.matrix
+    }
+
+    #[rustfmt::skip]
+    pub fn translate(tx: f32, ty: f32) -> Self {
+        Self::from_matrix([
+            [1.0, 0.0, tx],
+            [0.0, 1.0, ty],
+            [0.0, 0.0, 1.0],
+        ])
+    }
+
+    #[rustfmt::skip]
+    pub fn rotate(angle: f32) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::from_matrix([
+            [c,  -s,  0.0],
+            [s,   c,  0.0],
+            [0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotate_about(angle: f32, pivot: [f32; 2]) -> Self {
+        Self::about(pivot, Self::rotate(angle))
+    }
+
+    #[rustfmt::skip]
+    pub fn scale(sx: f32, sy: f32) -> Self {
+        Self::from_matrix([
+            [sx,  0.0, 0.0],
+            [0.0, sy,  0.0],
+            [0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Scales while keeping `fixed` in place.
+    pub fn scale_about(sx: f32, sy: f32, fixed: [f32; 2]) -> Self {
+        Self::about(fixed, Self::scale(sx, sy))
+    }
+
+    /// Reflection about the X axis.
+    pub fn reflect_x() -> Self {
+        Self::scale(1.0, -1.0)
+    }
+
+    /// Reflection about the Y axis.
+    pub fn reflect_y() -> Self {
+        Self::scale(-1.0, 1.0)
+    }
+
+    pub fn reflect_origin() -> Self {
+        Self::scale(-1.0, -1.0)
+    }
+
+    /// Reflection about the line y = x.
+    #[rustfmt::skip]
+    pub fn reflect_y_eq_x() -> Self {
+        Self::from_matrix([
+            [0.0, 1.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Reflection about the line y = -x.
+    #[rustfmt::skip]
+    pub fn reflect_y_eq_neg_x() -> Self {
+        Self::from_matrix([
+            [ 0.0, -1.0, 0.0],
+            [-1.0,  0.0, 0.0],
+            [ 0.0,  0.0, 1.0],
+        ])
+    }
+
+    /// Reflection about the line through `p` and `q`: move `p` to the origin,
+    /// rotate the line onto the X axis, reflect, and undo both steps.
+    ///
+    /// Panics if `p` and `q` are the same point, which has no line through it.
+    pub fn reflect_line(p: [f32; 2], q: [f32; 2]) -> Self {
+        assert_ne!(p, q, "a reflection line needs two distinct points");
+        let angle = (q[1] - p[1]).atan2(q[0] - p[0]);
+        Self::about(
+            p,
+            Self::rotate(-angle)
+                .then(Self::reflect_x())
+                .then(Self::rotate(angle)),
+        )
+    }
+
+    /// x' = x + shx * y
+    #[rustfmt::skip]
+    pub fn shear_x(shx: f32) -> Self {
+        Self::from_matrix([
+            [1.0, shx, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// y' = y + shy * x
+    #[rustfmt::skip]
+    pub fn shear_y(shy: f32) -> Self {
+        Self::from_matrix([
+            [1.0, 0.0, 0.0],
+            [shy, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Applies `self` first and `next` after it.
+    pub fn then(self, next: Self) -> Self {
+        next * self
+    }
+
+    /// `None` for a singular matrix. Tiny scales still invert, however small
+    /// their determinant.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.matrix;
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+
+        #[rustfmt::skip]
+        let adjugate = [
+            [ cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2),  cofactor(0, 1, 1, 2)],
+            [-cofactor(1, 2, 0, 2),  cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+            [ cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1),  cofactor(0, 1, 0, 1)],
+        ];
+
+        let determinant =
+            m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        Some(Self::from_matrix(
+            adjugate.map(|row| row.map(|v| v / determinant)),
+        ))
+    }
+
+    pub fn apply(&self, point: [f32; 2]) -> [f32; 2] {
+        let m = &self.matrix;
+        let w = m[2][0] * point[0] + m[2][1] * point[1] + m[2][2];
+        [
+            (m[0][0] * point[0] + m[0][1] * point[1] + m[0][2]) / w,
+            (m[1][0] * point[0] + m[1][1] * point[1] + m[1][2]) / w,
+        ]
+    }
+
+    pub fn apply_to(&self, points: &mut [[f32; 2]]) {
+        transform_points(points, self.matrix);
+    }
+
+    /// The matrix in the column-major order GLSL's `mat3` uses.
+    pub fn to_column_major(&self) -> [f32; 9] {
+        let m = &self.matrix;
+        [
+            m[0][0], m[1][0], m[2][0], m[0][1], m[1][1], m[2][1], m[0][2], m[1][2], m[2][2],
+        ]
+    }
+
+    /// Sets the `mat3` uniform at `location` of the program in use.
+    ///
+    /// # Safety
+    ///
+    /// The context `gl` was loaded from must be current on the calling thread.
+    pub unsafe fn upload(&self, gl: &gl::Gl, location: gl::types::GLint) {
+        gl.UniformMatrix3fv(location, 1, gl::FALSE, self.to_column_major().as_ptr());
+    }
+
+    fn about(point: [f32; 2], transform: Self) -> Self {
+        Self::translate(-point[0], -point[1])
+            .then(transform)
+            .then(Self::translate(point[0], point[1]))
+    }
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Transform2D {
+    type Output = Self;
+
+    /// Matrix product, so `a * b` applies `b` first.
+    fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (&self.matrix, &rhs.matrix);
+        Self::from_matrix(std::array::from_fn(|r| {
+            std::array::from_fn(|c| (0..3).map(|k| a[r][k] * b[k][c]).sum())
+        }))
+    }
+}
+
+impl From<Transform2D> for [[f32; 3]; 3] {
+    fn from(transform: Transform2D) -> Self {
+        transform.matrix
+    }
+}
+
+#[test]
+fn transform_points_test() {
+    let mut points = [[0.0, 0.0], [2.0, 1.0]];
+
+    // Rotate by 90 degrees, then translate by (10, 20).
+    #[rustfmt::skip]
+    let mat = [
+        [0.0, -1.0, 10.0],
+        [1.0,  0.0, 20.0],
+        [0.0,  0.0,  1.0],
+    ];
+
+    transform_points(&mut points, mat);
+
+    assert_eq!(points, [[10.0, 20.0], [9.0, 22.0]]);
+}
+
+#[test]
+fn transform_2d_test() {
+    use std::f32::consts::FRAC_PI_2;
+
+    let close = |a: [f32; 2], b: [f32; 2]| (a[0] - b[0]).abs() < 1e-4 && (a[1] - b[1]).abs() < 1e-4;
+
+    let rotation = Transform2D::rotate_about(FRAC_PI_2, [3.0, 4.0]);
+    assert!(close(rotation.apply([3.0, 4.0]), [3.0, 4.0]));
+    assert!(close(rotation.apply([4.0, 4.0]), [3.0, 5.0]));
+
+    let scaling = Transform2D::scale_about(2.0, 3.0, [1.0, 1.0]);
+    assert!(close(scaling.apply([1.0, 1.0]), [1.0, 1.0]));
+    assert!(close(scaling.apply([2.0, 2.0]), [3.0, 4.0]));
+
+    let diagonal = Transform2D::reflect_line([0.0, 0.0], [1.0, 1.0]);
+    assert!(close(
+        diagonal.apply([2.0, 5.0]),
+        Transform2D::reflect_y_eq_x().apply([2.0, 5.0])
+    ));
+    let anti_diagonal = Transform2D::reflect_line([1.0, -1.0], [-2.0, 2.0]);
+    assert!(close(anti_diagonal.apply([2.0, 5.0]), [-5.0, -2.0]));
+
+    assert!(close(
+        Transform2D::shear_x(0.5).apply([2.0, 2.0]),
+        [3.0, 2.0]
+    ));
+    assert!(close(
+        Transform2D::shear_y(0.5).apply([2.0, 2.0]),
+        [2.0, 3.0]
+    ));
+
+    let composed = Transform2D::shear_x(0.2)
+        .then(Transform2D::rotate(0.7))
+        .then(Transform2D::translate(5.0, -2.0));
+    let round_trip = composed.then(composed.inverse().unwrap());
+    assert!(close(round_trip.apply([7.0, 9.0]), [7.0, 9.0]));
+    assert_eq!(Transform2D::scale(0.0, 1.0).inverse(), None);
+
+    let tiny = Transform2D::scale(1e-4, 1e-4);
+    let round_trip = tiny.then(tiny.inverse().unwrap());
+    assert!(close(round_trip.apply([7.0, 9.0]), [7.0, 9.0]));
+
+    // GLSL's translation_mat had the offsets in its last column.
+    assert_eq!(
+        Transform2D::translate(-0.5, -0.2).to_column_major(),
+        [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, -0.5, -0.2, 1.0]
+    );
+}
+
+#[test]
+#[should_panic(expected = "two distinct points")]
+fn reflect_line_through_one_point_test() {
+    Transform2D::reflect_line([1.0, 2.0], [1.0, 2.0]);
+}
@@ -0,0 +1,266 @@
+use std::ops::Mul;
+
+use crate::opengl::gl;
+
+pub type Mat4 = [[f32; 4]; 4];
+
+/// A 3D transformation stored as a row-major homogeneous matrix.
+///
+/// Angles are in radians and follow the right-hand rule. Like
+/// [`Transform2D`](crate::transform::Transform2D), transformations compose
+/// with [`Transform3D::then`] in the order they are applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform3D {
+    matrix: Mat4,
+}
+
+impl Transform3D {
+    #[rustfmt::skip]
+    pub const IDENTITY: Self = Self::from_matrix([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    pub const fn from_matrix(matrix: Mat4) -> Self {
+        Self { matrix }
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        self.matrix
+    }
+
+    #[rustfmt::skip]
+    pub fn translate(tx: f32, ty: f32, tz: f32) -> Self {
+        Self::from_matrix([
+            [1.0, 0.0, 0.0, tx],
+            [0.0, 1.0, 0.0, ty],
+            [0.0, 0.0, 1.0, tz],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    #[rustfmt::skip]
+    pub fn scale(sx: f32, sy: f32, sz: f32) -> Self {
+        Self::from_matrix([
+            [sx,  0.0, 0.0, 0.0],
+            [0.0, sy,  0.0, 0.0],
+            [0.0, 0.0, sz,  0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn uniform_scale(s: f32) -> Self {
+        Self::scale(s, s, s)
+    }
+
+    #[rustfmt::skip]
+    pub fn rotate_x(angle: f32) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::from_matrix([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, c,   -s,  0.0],
+            [0.0, s,   c,   0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    #[rustfmt::skip]
+    pub fn rotate_y(angle: f32) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::from_matrix([
+            [c,   0.0, s,   0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [-s,  0.0, c,   0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    #[rustfmt::skip]
+    pub fn rotate_z(angle: f32) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::from_matrix([
+            [c,   -s,  0.0, 0.0],
+            [s,   c,   0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Rotation about `axis` through the origin (Rodrigues' formula).
+    #[rustfmt::skip]
+    pub fn rotate_axis(axis: [f32; 3], angle: f32) -> Self {
+        let [x, y, z] = normalize(axis);
+        let (s, c) = angle.sin_cos();
+        let t = 1.0 - c;
+        Self::from_matrix([
+            [t * x * x + c,     t * x * y - s * z, t * x * z + s * y, 0.0],
+            [t * x * y + s * z, t * y * y + c,     t * y * z - s * x, 0.0],
+            [t * x * z - s * y, t * y * z + s * x, t * z * z + c,     0.0],
+            [0.0,               0.0,               0.0,               1.0],
+        ])
+    }
+
+    /// Rotation about the line through `point` in the direction of `axis`.
+    pub fn rotate_about_axis(point: [f32; 3], axis: [f32; 3], angle: f32) -> Self {
+        let [px, py, pz] = point;
+        Self::translate(-px, -py, -pz)
+            .then(Self::rotate_axis(axis, angle))
+            .then(Self::translate(px, py, pz))
+    }
+
+    /// View transformation of a camera at `eye` looking at `target`, the same
+    /// matrix `gluLookAt` builds. The camera looks down its negative Z axis.
+    #[rustfmt::skip]
+    pub fn look_at(eye: [f32; 3], target: [f32; 3], up: [f32; 3]) -> Self {
+        let f = normalize(sub(target, eye));
+        let s = normalize(cross(f, up));
+        let u = cross(s, f);
+        Self::from_matrix([
+            [s[0],  s[1],  s[2],  -dot(s, eye)],
+            [u[0],  u[1],  u[2],  -dot(u, eye)],
+            [-f[0], -f[1], -f[2], dot(f, eye)],
+            [0.0,   0.0,   0.0,   1.0],
+        ])
+    }
+
+    /// Maps the box `[left, right] x [bottom, top] x [-near, -far]` in view
+    /// space to normalized device coordinates.
+    #[rustfmt::skip]
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        Self::from_matrix([
+            [2.0 / (right - left), 0.0, 0.0, -(right + left) / (right - left)],
+            [0.0, 2.0 / (top - bottom), 0.0, -(top + bottom) / (top - bottom)],
+            [0.0, 0.0, -2.0 / (far - near),  -(far + near) / (far - near)],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Perspective projection with a vertical field of view of `fovy`.
+    #[rustfmt::skip]
+    pub fn perspective(fovy: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let f = 1.0 / (fovy / 2.0).tan();
+        Self::from_matrix([
+            [f / aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, (far + near) / (near - far), 2.0 * far * near / (near - far)],
+            [0.0, 0.0, -1.0, 0.0],
+        ])
+    }
+
+    /// Applies `self` first and `next` after it.
+    pub fn then(self, next: Self) -> Self {
+        next * self
+    }
+
+    /// Transforms a point, including the perspective divide.
+    pub fn apply(&self, point: [f32; 3]) -> [f32; 3] {
+        let m = &self.matrix;
+        let row = |r: usize| m[r][0] * point[0] + m[r][1] * point[1] + m[r][2] * point[2] + m[r][3];
+        let w = row(3);
+        [row(0) / w, row(1) / w, row(2) / w]
+    }
+
+    /// The matrix in the column-major order GLSL's `mat4` uses.
+    pub fn to_column_major(&self) -> [f32; 16] {
+        std::array::from_fn(|i| self.matrix[i % 4][i / 4])
+    }
+
+    /// Sets the `mat4` uniform at `location` of the program in use.
+    ///
+    /// # Safety
+    ///
+    /// The context `gl` was loaded from must be current on the calling thread.
+    pub unsafe fn upload(&self, gl: &gl::Gl, location: gl::types::GLint) {
+        gl.UniformMatrix4fv(location, 1, gl::FALSE, self.to_column_major().as_ptr());
+    }
+}
+
+impl Default for Transform3D {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Transform3D {
+    type Output = Self;
+
+    /// Matrix product, so `a * b` applies `b` first.
+    fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (&self.matrix, &rhs.matrix);
+        Self::from_matrix(std::array::from_fn(|r| {
+            std::array::from_fn(|c| (0..4).map(|k| a[r][k] * b[k][c]).sum())
+        }))
+    }
+}
+
+impl From<Transform3D> for Mat4 {
+    fn from(transform: Transform3D) -> Self {
+        transform.matrix
+    }
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt();
+    [v[0] / length, v[1] / length, v[2] / length]
+}
+
+#[test]
+fn transform_3d_test() {
+    use std::f32::consts::FRAC_PI_2;
+
+    let close = |a: [f32; 3], b: [f32; 3]| (0..3).all(|i| (a[i] - b[i]).abs() < 1e-4);
+
+    let p = [1.0, 2.0, 3.0];
+    assert!(close(
+        Transform3D::rotate_axis([0.0, 0.0, 2.0], 0.6).apply(p),
+        Transform3D::rotate_z(0.6).apply(p)
+    ));
+    assert!(close(
+        Transform3D::rotate_axis([1.0, 0.0, 0.0], FRAC_PI_2).apply(p),
+        [1.0, -3.0, 2.0]
+    ));
+    assert!(close(
+        Transform3D::rotate_about_axis([1.0, 1.0, 0.0], [0.0, 0.0, 1.0], FRAC_PI_2)
+            .apply([2.0, 1.0, 5.0]),
+        [1.0, 2.0, 5.0]
+    ));
+
+    let view = Transform3D::look_at([0.0, 0.0, 5.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+    assert!(close(view.apply([0.0, 0.0, 5.0]), [0.0, 0.0, 0.0]));
+    assert!(close(view.apply([1.0, 2.0, 0.0]), [1.0, 2.0, -5.0]));
+
+    let projection = Transform3D::perspective(FRAC_PI_2, 2.0, 1.0, 10.0);
+    assert!(close(projection.apply([0.0, 1.0, -1.0]), [0.0, 1.0, -1.0]));
+    assert!(close(projection.apply([20.0, 0.0, -10.0]), [1.0, 0.0, 1.0]));
+
+    let projection = Transform3D::orthographic(-2.0, 2.0, -1.0, 1.0, 0.5, 10.0);
+    assert!(close(
+        projection.apply([2.0, -1.0, -0.5]),
+        [1.0, -1.0, -1.0]
+    ));
+    assert!(close(projection.apply([0.0, 0.0, -10.0]), [0.0, 0.0, 1.0]));
+
+    // GLSL's translation matrix had the offsets in its last column.
+    assert_eq!(
+        &Transform3D::translate(0.5, 0.25, 0.0).to_column_major()[12..],
+        &[0.5, 0.25, 0.0, 1.0]
+    );
+}
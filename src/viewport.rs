@@ -0,0 +1,138 @@
+use crate::transform::Transform2D;
+
+/// Axis aligned rectangle, in whatever coordinates the context calls for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub xmin: f32,
+    pub ymin: f32,
+    pub xmax: f32,
+    pub ymax: f32,
+}
+
+impl Rect {
+    pub fn new(xmin: f32, ymin: f32, xmax: f32, ymax: f32) -> Self {
+        Self {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.xmax - self.xmin
+    }
+
+    pub fn height(&self) -> f32 {
+        self.ymax - self.ymin
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.width() / self.height()
+    }
+
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        (self.xmin..=self.xmax).contains(&point[0]) && (self.ymin..=self.ymax).contains(&point[1])
+    }
+}
+
+/// Window-to-viewport mapping from world coordinates onto the drawing
+/// surface.
+///
+/// The world `window` is the region of world coordinates that is shown. It is
+/// mapped onto the device viewport, which is the whole surface, or the
+/// largest centred part of it with the same aspect ratio as the window when
+/// `preserve_aspect` is set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub window: Rect,
+    /// Surface size in pixels.
+    pub surface: [u32; 2],
+    pub preserve_aspect: bool,
+}
+
+impl Viewport {
+    pub fn new(window: Rect, width: u32, height: u32) -> Self {
+        Self {
+            window,
+            surface: [width, height],
+            preserve_aspect: true,
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.surface = [width, height];
+    }
+
+    /// Aspect ratio of the surface.
+    pub fn aspect(&self) -> f32 {
+        self.surface[0] as f32 / self.surface[1] as f32
+    }
+
+    /// The part of the surface the world window is mapped onto, in pixels.
+    pub fn device(&self) -> Rect {
+        let [width, height] = self.surface.map(|size| size as f32);
+        if !self.preserve_aspect {
+            return Rect::new(0.0, 0.0, width, height);
+        }
+
+        let scale = (width / self.window.width()).min(height / self.window.height());
+        let (device_width, device_height) =
+            (self.window.width() * scale, self.window.height() * scale);
+        let (xmin, ymin) = ((width - device_width) / 2.0, (height - device_height) / 2.0);
+        Rect::new(xmin, ymin, xmin + device_width, ymin + device_height)
+    }
+
+    /// Maps world coordinates to pixels on the surface.
+    pub fn window_to_device(&self) -> Transform2D {
+        let device = self.device();
+        Transform2D::translate(-self.window.xmin, -self.window.ymin)
+            .then(Transform2D::scale(
+                device.width() / self.window.width(),
+                device.height() / self.window.height(),
+            ))
+            .then(Transform2D::translate(device.xmin, device.ymin))
+    }
+
+    /// Maps world coordinates to normalized device coordinates of the whole
+    /// surface, ready to hand to OpenGL.
+    pub fn window_to_ndc(&self) -> Transform2D {
+        let [width, height] = self.surface.map(|size| size as f32);
+        self.window_to_device()
+            .then(Transform2D::scale(2.0 / width, 2.0 / height))
+            .then(Transform2D::translate(-1.0, -1.0))
+    }
+
+    pub fn to_ndc(&self, point: [f32; 2]) -> [f32; 2] {
+        self.window_to_ndc().apply(point)
+    }
+}
+
+impl Default for Viewport {
+    /// The 1600x1200 world the labs were written against, shown on a surface
+    /// of the same size.
+    fn default() -> Self {
+        Self::new(Rect::new(0.0, 0.0, 1600.0, 1200.0), 1600, 1200)
+    }
+}
+
+#[test]
+fn viewport_test() {
+    let close = |a: [f32; 2], b: [f32; 2]| (a[0] - b[0]).abs() < 1e-4 && (a[1] - b[1]).abs() < 1e-4;
+
+    let mut viewport = Viewport::default();
+    assert!(close(viewport.to_ndc([0.0, 0.0]), [-1.0, -1.0]));
+    assert!(close(viewport.to_ndc([800.0, 600.0]), [0.0, 0.0]));
+    assert!(close(viewport.to_ndc([1600.0, 1200.0]), [1.0, 1.0]));
+
+    // A wider surface letterboxes the window at the sides instead of
+    // stretching it.
+    viewport.resize(2400, 1200);
+    assert_eq!(viewport.device(), Rect::new(400.0, 0.0, 2000.0, 1200.0));
+    assert!(close(viewport.to_ndc([0.0, 0.0]), [-2.0 / 3.0, -1.0]));
+    assert!(close(viewport.to_ndc([800.0, 600.0]), [0.0, 0.0]));
+    assert!(close(viewport.to_ndc([1600.0, 1200.0]), [2.0 / 3.0, 1.0]));
+
+    viewport.preserve_aspect = false;
+    assert!(close(viewport.to_ndc([1600.0, 0.0]), [1.0, -1.0]));
+}
@@ -0,0 +1,223 @@
+//! Renders every rasterization algorithm into a framebuffer and compares the
+//! result against the reference images in `tests/golden`.
+//!
+//! References are plain PBM files so they can be read and reviewed as text.
+//! After an intentional change in output, regenerate them with
+//! `BIOPIX_BLESS=1 cargo test --test golden` and check the diff.
+
+use std::path::PathBuf;
+
+use biopix::circle::Circle;
+use biopix::ellipse::Ellipse;
+use biopix::fill::{FillRule, FilledPolygon};
+use biopix::framebuffer::{Framebuffer, BLACK, WHITE};
+use biopix::line::{Line, LineAlgorithm};
+use biopix::stroke::{LineCap, LineJoin, Stroke, StrokedPath};
+
+/// Endpoints around the border of a 33x33 image, so lines drawn from the
+/// centre cover all eight octants plus the axes and diagonals.
+const STAR: [[f32; 2]; 16] = [
+    [28.0, 16.0],
+    [28.0, 21.0],
+    [28.0, 28.0],
+    [21.0, 28.0],
+    [16.0, 28.0],
+    [11.0, 28.0],
+    [4.0, 28.0],
+    [4.0, 21.0],
+    [4.0, 16.0],
+    [4.0, 11.0],
+    [4.0, 4.0],
+    [11.0, 4.0],
+    [16.0, 4.0],
+    [21.0, 4.0],
+    [28.0, 4.0],
+    [28.0, 11.0],
+];
+
+fn star(algo: LineAlgorithm) -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(33, 33);
+    for end in STAR {
+        Line::new([16.0, 16.0], end, algo).rasterize(&mut framebuffer, WHITE);
+    }
+    framebuffer
+}
+
+#[test]
+fn line_dda() {
+    assert_golden("line_dda", &star(LineAlgorithm::DDA));
+}
+
+#[test]
+fn line_bresenham() {
+    assert_golden("line_bresenham", &star(LineAlgorithm::Bresenham));
+}
+
+#[test]
+fn line_midpoint() {
+    assert_golden("line_midpoint", &star(LineAlgorithm::Midpoint));
+}
+
+#[test]
+fn midpoint_circle() {
+    let mut framebuffer = Framebuffer::new(48, 48);
+    for radius in [0, 1, 6, 13, 21] {
+        Circle::new([24, 24], radius).rasterize(&mut framebuffer, WHITE);
+    }
+    assert_golden("midpoint_circle", &framebuffer);
+}
+
+#[test]
+fn midpoint_ellipse() {
+    let mut framebuffer = Framebuffer::new(48, 48);
+    for (a, b) in [(21, 9), (7, 20), (4, 4), (15, 1)] {
+        Ellipse::new([24, 24], a, b).rasterize(&mut framebuffer, WHITE);
+    }
+    assert_golden("midpoint_ellipse", &framebuffer);
+}
+
+#[test]
+fn stroke() {
+    // One zigzag per cap and join style, stacked from bottom to top.
+    let mut framebuffer = Framebuffer::new(64, 64);
+    let styles = [
+        (LineCap::Butt, LineJoin::Miter),
+        (LineCap::Square, LineJoin::Bevel),
+        (LineCap::Round, LineJoin::Round),
+    ];
+    for (row, (cap, join)) in styles.into_iter().enumerate() {
+        let y = 8.0 + 20.0 * row as f32;
+        let path = [[8.0, y], [24.0, y + 10.0], [40.0, y], [56.0, y + 6.0]];
+        let stroke = Stroke {
+            cap,
+            join,
+            ..Stroke::new(5.0)
+        };
+        StrokedPath::new(&path, false, &stroke).rasterize(&mut framebuffer, WHITE);
+    }
+    assert_golden("stroke", &framebuffer);
+}
+
+#[test]
+fn scanline_fill() {
+    // The same pentagram filled even-odd on the left and non-zero on the
+    // right.
+    let mut framebuffer = Framebuffer::new(64, 32);
+    for (centre, rule) in [(16.0, FillRule::EvenOdd), (48.0, FillRule::NonZero)] {
+        let star: Vec<[f32; 2]> = (0..5)
+            .map(|i| {
+                let angle = std::f32::consts::FRAC_PI_2 + i as f32 * 0.8 * std::f32::consts::PI;
+                [centre + 15.0 * angle.cos(), 16.0 + 15.0 * angle.sin()]
+            })
+            .collect();
+        FilledPolygon::new(&star, rule).rasterize(&mut framebuffer, WHITE);
+    }
+    assert_golden("scanline_fill", &framebuffer);
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.pbm", name))
+}
+
+/// Lit pixels as rows from top to bottom.
+fn to_bitmap(framebuffer: &Framebuffer) -> Vec<Vec<bool>> {
+    (0..framebuffer.height() as i32)
+        .rev()
+        .map(|y| {
+            (0..framebuffer.width() as i32)
+                .map(|x| framebuffer.get(x, y) != Some(BLACK))
+                .collect()
+        })
+        .collect()
+}
+
+fn write_pbm(bitmap: &[Vec<bool>]) -> String {
+    let mut pbm = format!("P1\n{} {}\n", bitmap[0].len(), bitmap.len());
+    for row in bitmap {
+        pbm.extend(row.iter().map(|&lit| if lit { '1' } else { '0' }));
+        pbm.push('\n');
+    }
+    pbm
+}
+
+fn read_pbm(source: &str) -> Vec<Vec<bool>> {
+    let mut tokens = source
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(str::split_whitespace);
+
+    assert_eq!(tokens.next(), Some("P1"), "only plain PBM is supported");
+    let width: usize = tokens.next().unwrap().parse().unwrap();
+    let height: usize = tokens.next().unwrap().parse().unwrap();
+
+    let pixels: Vec<bool> = tokens.flat_map(str::chars).map(|c| c == '1').collect();
+    assert_eq!(pixels.len(), width * height, "truncated PBM");
+
+    pixels.chunks(width).map(<[bool]>::to_vec).collect()
+}
+
+/// Expected and actual images side by side, followed by an overlay where `+`
+/// marks pixels only in the actual image and `-` pixels only in the expected.
+fn diff(expected: &[Vec<bool>], actual: &[Vec<bool>]) -> String {
+    let pixel = |lit: bool| if lit { '#' } else { '.' };
+
+    let mut out = String::from("expected | actual | diff\n");
+    for (e, a) in expected.iter().zip(actual) {
+        out.extend(e.iter().map(|&lit| pixel(lit)));
+        out.push_str(" | ");
+        out.extend(a.iter().map(|&lit| pixel(lit)));
+        out.push_str(" | ");
+        out.extend(e.iter().zip(a).map(|(&e, &a)| match (e, a) {
+            (false, true) => '+',
+            (true, false) => '-',
+            (lit, _) => pixel(lit),
+        }));
+        out.push('\n');
+    }
+    out
+}
+
+fn assert_golden(name: &str, framebuffer: &Framebuffer) {
+    let path = golden_path(name);
+    let actual = to_bitmap(framebuffer);
+
+    if std::env::var_os("BIOPIX_BLESS").is_some() {
+        std::fs::write(&path, write_pbm(&actual)).unwrap();
+        return;
+    }
+
+    let source = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing reference image {}, run with BIOPIX_BLESS=1 to create it",
+            path.display()
+        )
+    });
+    let expected = read_pbm(&source);
+
+    assert_eq!(
+        (expected[0].len(), expected.len()),
+        (actual[0].len(), actual.len()),
+        "{}: image size differs from the reference",
+        name
+    );
+
+    let mismatched = expected
+        .iter()
+        .flatten()
+        .zip(actual.iter().flatten())
+        .filter(|(e, a)| e != a)
+        .count();
+
+    if mismatched > 0 {
+        panic!(
+            "{}: {} pixels differ from {}\n{}",
+            name,
+            mismatched,
+            path.display(),
+            diff(&expected, &actual)
+        );
+    }
+}
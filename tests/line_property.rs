@@ -0,0 +1,105 @@
+//! Checks the aliased line algorithms against each other and against the
+//! ideal line for a few thousand random segments in every octant.
+
+use biopix::line::{Line, LineAlgorithm};
+
+const ALGORITHMS: [LineAlgorithm; 3] = [
+    LineAlgorithm::DDA,
+    LineAlgorithm::Bresenham,
+    LineAlgorithm::Midpoint,
+];
+
+/// xorshift64, so the segments are the same on every run without pulling in
+/// a random number crate.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform in `-range..=range`.
+    fn coordinate(&mut self, range: i32) -> i32 {
+        (self.next() % (2 * range as u64 + 1)) as i32 - range
+    }
+
+    /// Up to half a pixel away from an integer coordinate.
+    fn fraction(&mut self) -> f32 {
+        (self.next() % 1000) as f32 / 2000.0
+            * if self.next().is_multiple_of(2) {
+                1.0
+            } else {
+                -1.0
+            }
+    }
+}
+
+fn pixels(line: &Line) -> Vec<[i32; 2]> {
+    line.points()
+        .iter()
+        .map(|&[x, y]| [x as i32, y as i32])
+        .collect()
+}
+
+/// One pixel per step along the major axis, from `from` to `to`, each at
+/// most half a pixel off the ideal line along both axes. The comparison is
+/// done scaled by `2 * steps` so it is exact.
+fn assert_ideal(from: [i32; 2], to: [i32; 2], pixels: &[[i32; 2]], algo: LineAlgorithm) {
+    let d = [(to[0] - from[0]) as i64, (to[1] - from[1]) as i64];
+    let steps = d[0].abs().max(d[1].abs());
+
+    assert_eq!(
+        pixels.len(),
+        steps as usize + 1,
+        "{algo:?} {from:?} -> {to:?}"
+    );
+    assert_eq!(pixels.first(), Some(&from), "{algo:?} {from:?} -> {to:?}");
+    assert_eq!(pixels.last(), Some(&to), "{algo:?} {from:?} -> {to:?}");
+
+    for (i, pixel) in pixels.iter().enumerate() {
+        for axis in 0..2 {
+            let offset = (pixel[axis] - from[axis]) as i64;
+            let error = 2 * (offset * steps - i as i64 * d[axis]).abs();
+            assert!(
+                error <= steps.max(1),
+                "{algo:?} {from:?} -> {to:?}: pixel {i} at {pixel:?} is off the line"
+            );
+        }
+    }
+}
+
+#[test]
+fn line_algorithms_agree() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for _ in 0..5000 {
+        let from = [rng.coordinate(100), rng.coordinate(100)];
+        // Short segments make the degenerate cases and the axes come up often.
+        let range = if rng.next().is_multiple_of(4) { 2 } else { 100 };
+        let to = [
+            from[0] + rng.coordinate(range),
+            from[1] + rng.coordinate(range),
+        ];
+
+        let x1 = [from[0] as f32, from[1] as f32];
+        let x2 = [to[0] as f32, to[1] as f32];
+        let reference = pixels(&Line::new(x1, x2, LineAlgorithm::Bresenham));
+
+        for algo in ALGORITHMS {
+            let line = pixels(&Line::new(x1, x2, algo));
+            assert_ideal(from, to, &line, algo);
+            assert_eq!(line, reference, "{algo:?} {from:?} -> {to:?}");
+
+            // Fractional endpoints are snapped to the pixel they fall in.
+            let fractional = [
+                [x1[0] + rng.fraction(), x1[1] + rng.fraction()],
+                [x2[0] + rng.fraction(), x2[1] + rng.fraction()],
+            ];
+            let snapped = pixels(&Line::new(fractional[0], fractional[1], algo));
+            assert_eq!(snapped, line, "{algo:?} {fractional:?}");
+        }
+    }
+}